@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::backend::PoolBackend;
+use crate::config::PoolConfig;
+use crate::pool::{ArrayPool, ArrayPoolError, BorrowingSlice};
+
+/// A compile-time alignment in bytes.
+pub trait Alignment {
+    /// The alignment, a power of two.
+    const ALIGN: usize;
+}
+
+macro_rules! alignments {
+    ($($name:ident = $align:literal),*) => {
+        $(
+            #[doc = concat!("Align buffers to ", stringify!($align), " bytes.")]
+            #[derive(Copy, Clone, Debug, Default)]
+            pub struct $name;
+
+            impl Alignment for $name {
+                const ALIGN: usize = $align;
+            }
+        )*
+    };
+}
+
+alignments!(Align16 = 16, Align32 = 32, Align64 = 64);
+
+/// An `ArrayPool` whose arrays are all aligned to at least `A::ALIGN` bytes.
+///
+/// A `PooledVec<T, AlignedPool<T, A>>` keeps its buffer aligned across
+/// growth, so its content can be used with SIMD loads and stores.
+pub struct AlignedPool<T: Send, A: Alignment> {
+    pool: ArrayPool<T>,
+    _alignment: PhantomData<A>,
+}
+
+impl<T: Send, A: Alignment> AlignedPool<T, A> {
+    /// Create a new `AlignedPool` with `max_power`.
+    ///
+    /// See `ArrayPool::with_max_power`.
+    pub fn with_max_power(max_power: u8) -> Result<Self, ArrayPoolError> {
+        Self::from_config(PoolConfig { max_power, ..PoolConfig::default() })
+    }
+
+    /// Create a new `AlignedPool` from `config`, overriding its alignment.
+    pub fn from_config(config: PoolConfig) -> Result<Self, ArrayPoolError> {
+        Ok(Self {
+            pool: ArrayPool::from_config(PoolConfig { alignment: Some(A::ALIGN), ..config })?,
+            _alignment: PhantomData,
+        })
+    }
+
+    /// Create a new `AlignedPool` with `max_power` of `target_pointer_width - 1`.
+    pub fn new() -> Self {
+        Self::from_config(PoolConfig::default()).unwrap()
+    }
+}
+
+impl<T: Send, A: Alignment> Default for AlignedPool<T, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send, A: Alignment> Deref for AlignedPool<T, A> {
+    type Target = ArrayPool<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
+
+impl<T: Send, A: Alignment> PoolBackend<T> for AlignedPool<T, A> {
+    type Buffer = BorrowingSlice<T>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.pool.rent_uninitialized(minimum_capacity)
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.pool.rent_minimum_uninitialized()
+    }
+
+    fn return_buffer(&self, buffer: Self::Buffer) {
+        self.pool.return_buffer(buffer)
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        PoolBackend::expand_buffer(&self.pool, buffer)
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        PoolBackend::shrink_buffer(&self.pool, buffer)
+    }
+
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        self.pool.tag_buffer(buffer, tag)
+    }
+
+    fn min_size(&self) -> usize {
+        self.pool.min_size()
+    }
+
+    fn max_size(&self) -> usize {
+        self.pool.max_size()
+    }
+}
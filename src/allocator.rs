@@ -0,0 +1,140 @@
+use std::alloc::Layout;
+use std::panic::RefUnwindSafe;
+
+/// Allocates the arrays of a pool in place of the global allocator, see
+/// `ArrayPool::with_allocator`.
+///
+/// This is the hook for memory needing special treatment, such as
+/// page-locked staging buffers for DMA or GPU transfers.
+pub trait BufferAllocator: Send + Sync + RefUnwindSafe {
+    /// Allocate memory for `layout`, zeroed if needed. Returns null on failure.
+    ///
+    /// # Safety
+    ///
+    /// `layout` has a non-zero size.
+    unsafe fn allocate(&self, layout: Layout, zeroed: bool) -> *mut u8;
+
+    /// Free memory returned by `allocate` with the same `layout`.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` was allocated by this allocator with `layout`.
+    unsafe fn deallocate(&self, pointer: *mut u8, layout: Layout);
+}
+
+#[cfg(feature = "mlock")]
+mod locked {
+    use std::alloc::{alloc, alloc_zeroed, dealloc, Layout};
+    use std::ffi::c_void;
+    use std::ptr::write_bytes;
+
+    use super::BufferAllocator;
+
+    #[cfg(unix)]
+    extern "C" {
+        fn mlock(address: *const c_void, length: usize) -> i32;
+        fn munlock(address: *const c_void, length: usize) -> i32;
+        fn getpagesize() -> i32;
+    }
+
+    #[cfg(windows)]
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn VirtualLock(address: *mut c_void, length: usize) -> i32;
+        fn VirtualUnlock(address: *mut c_void, length: usize) -> i32;
+        fn GetSystemInfo(info: *mut SystemInfo);
+    }
+
+    #[cfg(windows)]
+    #[repr(C)]
+    struct SystemInfo {
+        processor_architecture: u16,
+        reserved: u16,
+        page_size: u32,
+        minimum_application_address: *mut c_void,
+        maximum_application_address: *mut c_void,
+        active_processor_mask: usize,
+        number_of_processors: u32,
+        processor_type: u32,
+        allocation_granularity: u32,
+        processor_level: u16,
+        processor_revision: u16,
+    }
+
+    /// Gets the size of a memory page.
+    #[cfg(unix)]
+    pub(crate) fn page_size() -> usize {
+        unsafe { getpagesize() as usize }
+    }
+
+    /// Gets the size of a memory page.
+    #[cfg(windows)]
+    pub(crate) fn page_size() -> usize {
+        let mut info = std::mem::MaybeUninit::<SystemInfo>::zeroed();
+        unsafe {
+            GetSystemInfo(info.as_mut_ptr());
+            info.assume_init().page_size as usize
+        }
+    }
+
+    /// Grow `layout` to whole pages, since locks don't nest: unlocking an
+    /// array must not unlock a page shared with another live array.
+    fn page_layout(layout: Layout) -> Layout {
+        let page = page_size();
+        Layout::from_size_align(layout.size().next_multiple_of(page), layout.align().max(page))
+            .expect("array size overflows when rounded to whole pages")
+    }
+
+    #[cfg(unix)]
+    unsafe fn lock(pointer: *mut u8, length: usize) -> bool {
+        mlock(pointer as *const c_void, length) == 0
+    }
+
+    #[cfg(unix)]
+    unsafe fn unlock(pointer: *mut u8, length: usize) {
+        munlock(pointer as *const c_void, length);
+    }
+
+    #[cfg(windows)]
+    unsafe fn lock(pointer: *mut u8, length: usize) -> bool {
+        VirtualLock(pointer as *mut c_void, length) != 0
+    }
+
+    #[cfg(windows)]
+    unsafe fn unlock(pointer: *mut u8, length: usize) {
+        VirtualUnlock(pointer as *mut c_void, length);
+    }
+
+    /// Allocates page-locked arrays with `mlock` (`VirtualLock` on Windows),
+    /// so they are never swapped out.
+    ///
+    /// Every array takes whole pages of its own. An allocation fails if its
+    /// pages can't be locked, e.g. past the process' locked memory limit.
+    /// Arrays are zeroed before their pages are unlocked and freed.
+    #[derive(Copy, Clone, Debug, Default)]
+    pub struct LockedAllocator;
+
+    impl BufferAllocator for LockedAllocator {
+        unsafe fn allocate(&self, layout: Layout, zeroed: bool) -> *mut u8 {
+            let layout = page_layout(layout);
+            let pointer = if zeroed { alloc_zeroed(layout) } else { alloc(layout) };
+            if !pointer.is_null() && !lock(pointer, layout.size()) {
+                dealloc(pointer, layout);
+                return std::ptr::null_mut();
+            }
+            pointer
+        }
+
+        unsafe fn deallocate(&self, pointer: *mut u8, layout: Layout) {
+            let layout = page_layout(layout);
+            write_bytes(pointer, 0, layout.size());
+            unlock(pointer, layout.size());
+            dealloc(pointer, layout);
+        }
+    }
+}
+
+#[cfg(feature = "mlock")]
+pub use locked::LockedAllocator;
+#[cfg(all(feature = "mlock", test))]
+pub(crate) use locked::page_size;
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use thread_local::ThreadLocal;
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+use crate::vec::{PooledVec, PushError};
+
+/// A log many threads append to at once, in segments rented from a pool.
+///
+/// Every thread fills its own open segment, so appending threads only
+/// contend when a segment fills up and joins the completed ones. A reader
+/// takes the completed segments with `take_completed`, each returns to the
+/// pool once dropped. Elements keep their order within a thread, not
+/// across threads, and a new thread may carry on the open segment of one
+/// that exited.
+pub struct PooledAppendLog<T: Send, P: PoolBackend<T> + Send + Sync = ArrayPool<T>> where P::Buffer: Send {
+    pool: Arc<P>,
+    segment_capacity: usize,
+    open: ThreadLocal<Mutex<PooledVec<T, P>>>,
+    completed: Mutex<VecDeque<PooledVec<T, P>>>,
+}
+
+impl<T: Send, P: PoolBackend<T> + Send + Sync> PooledAppendLog<T, P> where P::Buffer: Send {
+    /// Create a new log of segments holding at least `segment_capacity`
+    /// elements each.
+    pub fn create(pool: Arc<P>, segment_capacity: usize) -> Self {
+        Self {
+            pool,
+            segment_capacity: segment_capacity.max(1),
+            open: ThreadLocal::new(),
+            completed: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Append `value` to the open segment of the current thread, renting
+    /// a new segment if needed.
+    ///
+    /// Hand `value` back if the pool can't supply a segment.
+    pub fn try_append(&self, value: T) -> Result<(), PushError<T>> {
+        let open = self.open.get_or(|| Mutex::new(PooledVec::create(self.pool.clone())));
+        let mut segment = open.lock().unwrap();
+        if segment.capacity() == 0 {
+            if let Err(error) = segment.try_reserve(self.segment_capacity) {
+                return Err(PushError { value, error });
+            }
+        }
+        segment.push(value);
+        if segment.len() == segment.capacity() {
+            let full = std::mem::replace(&mut *segment, PooledVec::create(self.pool.clone()));
+            drop(segment);
+            self.completed.lock().unwrap().push_back(full);
+        }
+        Ok(())
+    }
+
+    /// Append `value` to the open segment of the current thread, renting
+    /// a new segment if needed.
+    pub fn append(&self, value: T) {
+        if self.try_append(value).is_err() {
+            panic!("Could not borrow a buffer from given array pool");
+        }
+    }
+
+    /// Complete the open segment of every thread that holds any element,
+    /// so readers can take them.
+    pub fn seal(&self) {
+        for open in self.open.iter() {
+            let mut segment = open.lock().unwrap();
+            if segment.is_empty() { continue; }
+            let partial = std::mem::replace(&mut *segment, PooledVec::create(self.pool.clone()));
+            drop(segment);
+            self.completed.lock().unwrap().push_back(partial);
+        }
+    }
+
+    /// Gets the number of completed segments waiting for a reader.
+    pub fn completed_segments(&self) -> usize {
+        self.completed.lock().unwrap().len()
+    }
+
+    /// Take the completed segments in the order they were completed.
+    pub fn take_completed(&self) -> impl Iterator<Item = PooledVec<T, P>> {
+        std::mem::take(&mut *self.completed.lock().unwrap()).into_iter()
+    }
+}
@@ -0,0 +1,119 @@
+use std::ops::DerefMut;
+
+use crate::guard::InitGuard;
+use crate::hooks::{Layered, PoolHooks};
+use crate::pool::{ArrayPool, ArrayPoolError, BorrowingSlice};
+
+/// Common interface of the pools that collections rent their buffers from.
+///
+/// Buffers rented through this trait are treated as uninitialized: the pool
+/// never drops their elements, the collection owning a buffer is responsible
+/// for dropping whatever it has written into it.
+pub trait PoolBackend<T> {
+    /// The buffer type handed out by this pool.
+    ///
+    /// Dropping a buffer returns it to the pool that rented it.
+    type Buffer: DerefMut<Target = [T]>;
+
+    /// Rent a buffer with `minimum_capacity`.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned buffer may be uninitialized.
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError>;
+
+    /// Rent a buffer with the smallest supported capacity.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned buffer may be uninitialized.
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError>;
+
+    /// Return `buffer` to this pool. Its elements won't be dropped.
+    fn return_buffer(&self, buffer: Self::Buffer) {
+        drop(buffer);
+    }
+
+    /// Move the content of `buffer` into a buffer with twice its capacity.
+    ///
+    /// # Safety
+    ///
+    /// New slots won't be initialized.
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError>;
+
+    /// Move the first half of `buffer` into a buffer with half its capacity.
+    /// Return `buffer` itself if no smaller buffer is available.
+    ///
+    /// # Safety
+    ///
+    /// Slots in the second half won't be dropped.
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer;
+
+    /// Attribute `buffer` to `tag` in the usage statistics of this pool,
+    /// if it keeps any. Does nothing by default.
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        let _ = (buffer, tag);
+    }
+
+    /// Gets the smallest supported capacity.
+    fn min_size(&self) -> usize;
+
+    /// Gets the largest supported capacity.
+    fn max_size(&self) -> usize;
+
+    /// Wrap this pool so `hooks` run on every buffer rented from and returned to it.
+    fn layer<H: PoolHooks<T>>(self, hooks: H) -> Layered<Self, H> where Self: Sized {
+        Layered::new(self, hooks)
+    }
+}
+
+/// Rent a buffer with `minimum_capacity` from `pool`, each element is
+/// initialized by the default constructor.
+pub(crate) fn rent_default<T: Default, P: PoolBackend<T>>(pool: &P, minimum_capacity: usize) -> Result<P::Buffer, ArrayPoolError> {
+    let mut buffer = unsafe { pool.rent_uninitialized(minimum_capacity) }?;
+    let len = buffer.len();
+    // Rented buffers are uninitialized, avoid dropping their content
+    let mut guard = unsafe { InitGuard::new(&mut buffer) };
+    for _ in 0..len {
+        guard.push(T::default());
+    }
+    guard.finish();
+    Ok(buffer)
+}
+
+impl<T: Send> PoolBackend<T> for ArrayPool<T> {
+    type Buffer = BorrowingSlice<T>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.rent_or_create_uninitialized(minimum_capacity, false)
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.rent_or_create_minimum_uninitialized(false)
+    }
+
+    fn return_buffer(&self, mut buffer: Self::Buffer) {
+        buffer.initialized = false;
+        drop(buffer);
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        ArrayPool::expand_buffer(self, buffer)
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        ArrayPool::shrink_buffer(self, buffer)
+    }
+
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        buffer.set_tag(tag);
+    }
+
+    fn min_size(&self) -> usize {
+        ArrayPool::min_size(self)
+    }
+
+    fn max_size(&self) -> usize {
+        ArrayPool::max_size(self)
+    }
+}
@@ -0,0 +1,84 @@
+use std::io;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::backend::PoolBackend;
+use crate::endian::PutBytes;
+use crate::pool::ArrayPool;
+use crate::vec::PooledVec;
+
+const LENGTH_SIZE: usize = 4;
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A codec for frames prefixed by their length as a big-endian `u32`,
+/// decoding each frame into a byte vector rented from a pool.
+///
+/// Frames return their storage to the pool once dropped, instead of
+/// splitting the read buffer like `LengthDelimitedCodec`.
+pub struct PooledLengthCodec<P: PoolBackend<u8> = ArrayPool<u8>> {
+    pool: Arc<P>,
+    max_frame_length: usize,
+}
+
+fn frame_too_long(len: usize, max: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("frame of {len} bytes exceeds the maximum of {max}"))
+}
+
+impl<P: PoolBackend<u8>> PooledLengthCodec<P> {
+    /// Create a new codec accepting frames of up to 8 MiB.
+    pub fn new(pool: Arc<P>) -> Self {
+        Self::with_max_frame_length(pool, DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    /// Create a new codec accepting frames of up to `max_frame_length` bytes.
+    pub fn with_max_frame_length(pool: Arc<P>, max_frame_length: usize) -> Self {
+        Self {
+            pool,
+            max_frame_length: max_frame_length.min(u32::MAX as usize),
+        }
+    }
+
+    /// Gets the longest accepted frame.
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+}
+
+impl<P: PoolBackend<u8>> Decoder for PooledLengthCodec<P> {
+    type Item = PooledVec<u8, P>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < LENGTH_SIZE { return Ok(None); }
+        let len = u32::from_be_bytes(src[..LENGTH_SIZE].try_into().unwrap()) as usize;
+        if len > self.max_frame_length {
+            return Err(frame_too_long(len, self.max_frame_length));
+        }
+        if src.len() < LENGTH_SIZE + len {
+            src.reserve(LENGTH_SIZE + len - src.len());
+            return Ok(None);
+        }
+        let mut frame = PooledVec::create(self.pool.clone());
+        frame.try_reserve(len)?;
+        frame.put_slice(&src[LENGTH_SIZE..LENGTH_SIZE + len]);
+        src.advance(LENGTH_SIZE + len);
+        Ok(Some(frame))
+    }
+}
+
+impl<P: PoolBackend<u8>, B: Deref<Target = [u8]>> Encoder<B> for PooledLengthCodec<P> {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: B, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.len() > self.max_frame_length {
+            return Err(frame_too_long(item.len(), self.max_frame_length));
+        }
+        dst.reserve(LENGTH_SIZE + item.len());
+        dst.put_u32(item.len() as u32);
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
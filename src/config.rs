@@ -0,0 +1,169 @@
+use std::env;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::pool::Sharding;
+
+/// Settings of an `ArrayPool`, see `ArrayPool::from_config`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Size classes have length 2^n (3 <= n < `max_power`).
+    pub max_power: u8,
+    /// How the cached arrays of each size class are split into chains.
+    pub sharding: Sharding,
+    /// Free returned arrays of at least this many bytes instead of caching them.
+    pub discard_above: Option<usize>,
+    /// Maximum total size in bytes of the cached arrays, arrays returned
+    /// past this budget are freed.
+    pub max_cached_bytes: Option<usize>,
+    /// Maximum number of arrays each thread caches per size class, arrays
+    /// returned past this cap go to a chain shared by every thread.
+    pub max_cached_per_thread: Option<usize>,
+    /// Number of consecutive rents a thread serves by allocating when its
+    /// own chain is empty, before looking for an array in other threads'
+    /// chains. Stealing starts on the first miss with `0`.
+    pub steal_after_misses: usize,
+    /// Zero the memory of arrays when they are returned.
+    pub scrub: bool,
+    /// Minimum alignment in bytes of every array, must be a power of two.
+    /// Arrays are aligned for `T` if `None`.
+    pub alignment: Option<usize>,
+    /// Allocate this many arrays per size class up front and never
+    /// allocate afterward, rents fail once they are all taken.
+    pub fixed_per_class: Option<usize>,
+    /// Make the pool behave the same across runs, for tests: chains are
+    /// per thread and keyed by the ids of `pool::set_thread_id`, stealing
+    /// always scans chains in key order and rents never skip a lookup.
+    pub deterministic: bool,
+    /// Allocate arrays page-locked with `LockedAllocator`, so their content
+    /// is never swapped to disk. Ignored by `ArrayPool::with_allocator`.
+    ///
+    /// Requires the `mlock` feature, pools fail to build with
+    /// `ArrayPoolError::LockingUnsupported` otherwise.
+    pub lock_memory: bool,
+}
+
+/// An environment variable holding a value `PoolConfig::from_env` can't parse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolConfigError {
+    /// Name of the variable.
+    pub variable: String,
+    /// Its unparsable value.
+    pub value: String,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_power: (usize::BITS - 1) as u8,
+            sharding: Sharding::PerThread,
+            discard_above: None,
+            max_cached_bytes: None,
+            max_cached_per_thread: None,
+            steal_after_misses: 0,
+            scrub: false,
+            alignment: None,
+            fixed_per_class: None,
+            deterministic: false,
+            lock_memory: false,
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_sharding(value: &str) -> Option<Sharding> {
+    match value.to_ascii_lowercase().as_str() {
+        "per-thread" | "thread" => Some(Sharding::PerThread),
+        "per-cpu" | "cpu" => Some(Sharding::PerCpu),
+        _ => None,
+    }
+}
+
+fn parse_limit(value: &str) -> Option<Option<usize>> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "none" => Some(None),
+        value => usize::from_str(value).ok().map(Some),
+    }
+}
+
+impl PoolConfig {
+    /// Read a config from the environment, keeping the default of every
+    /// setting whose variable is unset.
+    ///
+    /// With a `prefix` of `APP_POOL`, the variables read are:
+    /// - `APP_POOL_MAX_POWER`: a number, see `max_power`
+    /// - `APP_POOL_SHARDING`: `per-thread` or `per-cpu`
+    /// - `APP_POOL_DISCARD_ABOVE`: a number of bytes or `none`
+    /// - `APP_POOL_MAX_BYTES`: a number of bytes or `none`
+    /// - `APP_POOL_MAX_PER_THREAD`: a number of arrays or `none`
+    /// - `APP_POOL_STEAL_AFTER`: a number of rents
+    /// - `APP_POOL_SCRUB`: `true` or `false`
+    /// - `APP_POOL_ALIGNMENT`: a number of bytes or `none`
+    /// - `APP_POOL_FIXED_PER_CLASS`: a number of arrays or `none`
+    /// - `APP_POOL_DETERMINISTIC`: `true` or `false`
+    /// - `APP_POOL_LOCK_MEMORY`: `true` or `false`
+    pub fn from_env(prefix: &str) -> Result<Self, PoolConfigError> {
+        let mut config = Self::default();
+        if let Some(value) = read_var(prefix, "MAX_POWER", |value| u8::from_str(value).ok())? {
+            config.max_power = value;
+        }
+        if let Some(value) = read_var(prefix, "SHARDING", parse_sharding)? {
+            config.sharding = value;
+        }
+        if let Some(value) = read_var(prefix, "DISCARD_ABOVE", parse_limit)? {
+            config.discard_above = value;
+        }
+        if let Some(value) = read_var(prefix, "MAX_BYTES", parse_limit)? {
+            config.max_cached_bytes = value;
+        }
+        if let Some(value) = read_var(prefix, "MAX_PER_THREAD", parse_limit)? {
+            config.max_cached_per_thread = value;
+        }
+        if let Some(value) = read_var(prefix, "STEAL_AFTER", |value| usize::from_str(value).ok())? {
+            config.steal_after_misses = value;
+        }
+        if let Some(value) = read_var(prefix, "SCRUB", parse_bool)? {
+            config.scrub = value;
+        }
+        if let Some(value) = read_var(prefix, "ALIGNMENT", parse_limit)? {
+            config.alignment = value;
+        }
+        if let Some(value) = read_var(prefix, "FIXED_PER_CLASS", parse_limit)? {
+            config.fixed_per_class = value;
+        }
+        if let Some(value) = read_var(prefix, "DETERMINISTIC", parse_bool)? {
+            config.deterministic = value;
+        }
+        if let Some(value) = read_var(prefix, "LOCK_MEMORY", parse_bool)? {
+            config.lock_memory = value;
+        }
+        Ok(config)
+    }
+}
+
+fn read_var<V, F: FnOnce(&str) -> Option<V>>(prefix: &str, name: &str, parse: F) -> Result<Option<V>, PoolConfigError> {
+    let variable = format!("{prefix}_{name}");
+    match env::var(&variable) {
+        Ok(value) => match parse(value.trim()) {
+            Some(parsed) => Ok(Some(parsed)),
+            None => Err(PoolConfigError { variable, value }),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+impl Display for PoolConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid value {:?} for {}", self.value, self.variable)
+    }
+}
+
+impl Error for PoolConfigError {}
@@ -0,0 +1,156 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+use crate::vec::PooledVec;
+
+const ZEROS: [u8; 256] = [0; 256];
+
+/// Reads, writes and seeks over a `PooledVec<u8>`, like `io::Cursor`
+/// over a `Vec<u8>`.
+///
+/// Writing past the end grows the vector through its pool, filling any
+/// gap left by seeking past the end with zeros.
+pub struct PooledVecCursor<P: PoolBackend<u8> = ArrayPool<u8>> {
+    vec: PooledVec<u8, P>,
+    position: u64,
+}
+
+/// Reads a `PooledVec<u8>` from the front, handing its buffer back to the
+/// pool as soon as every byte has been read.
+pub struct DrainingReader<P: PoolBackend<u8> = ArrayPool<u8>> {
+    vec: Option<PooledVec<u8, P>>,
+    offset: usize,
+}
+
+impl<P: PoolBackend<u8>> PooledVecCursor<P> {
+    /// Create a new cursor at the start of `vec`.
+    pub fn new(vec: PooledVec<u8, P>) -> Self {
+        Self { vec, position: 0 }
+    }
+
+    /// Gets the underlying vector.
+    pub fn into_inner(self) -> PooledVec<u8, P> {
+        self.vec
+    }
+
+    /// Gets a reference to the underlying vector.
+    pub fn get_ref(&self) -> &PooledVec<u8, P> {
+        &self.vec
+    }
+
+    /// Gets a mutable reference to the underlying vector.
+    pub fn get_mut(&mut self) -> &mut PooledVec<u8, P> {
+        &mut self.vec
+    }
+
+    /// Gets the position of this cursor.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Set the position of this cursor, possibly past the end.
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+}
+
+impl<P: PoolBackend<u8>> Read for PooledVecCursor<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = self.position.min(self.vec.len() as u64) as usize;
+        let available = &self.vec[start..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<P: PoolBackend<u8>> Write for PooledVecCursor<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let position = usize::try_from(self.position)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "cursor position exceeds the address space"))?;
+        let end = position.checked_add(buf.len())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "write exceeds the address space"))?;
+        self.vec.try_reserve(end.saturating_sub(self.vec.len()))?;
+        while self.vec.len() < position {
+            let gap = (position - self.vec.len()).min(ZEROS.len());
+            self.vec.extend_from_slice_copy(&ZEROS[..gap]);
+        }
+        let overwritten = (self.vec.len() - position).min(buf.len());
+        self.vec[position..position + overwritten].copy_from_slice(&buf[..overwritten]);
+        self.vec.extend_from_slice_copy(&buf[overwritten..]);
+        self.position = end as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<P: PoolBackend<u8>> Seek for PooledVecCursor<P> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(offset) => (self.vec.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position")),
+        }
+    }
+}
+
+impl<P: PoolBackend<u8>> DrainingReader<P> {
+    /// Create a new reader consuming `vec`.
+    pub fn new(vec: PooledVec<u8, P>) -> Self {
+        let mut reader = Self { vec: Some(vec), offset: 0 };
+        reader.release_if_exhausted();
+        reader
+    }
+
+    /// Gets the bytes left to read.
+    pub fn remaining(&self) -> &[u8] {
+        self.vec.as_ref().map_or(&[], |vec| &vec[self.offset..])
+    }
+
+    /// Check whether every byte has been read and the buffer returned.
+    pub fn is_exhausted(&self) -> bool {
+        self.vec.is_none()
+    }
+
+    fn release_if_exhausted(&mut self) {
+        if self.vec.as_ref().is_some_and(|vec| self.offset >= vec.len()) {
+            self.vec = None;
+        }
+    }
+}
+
+impl<P: PoolBackend<u8>> Read for DrainingReader<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.remaining();
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl<P: PoolBackend<u8>> BufRead for DrainingReader<P> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.offset += amount.min(self.remaining().len());
+        self.release_if_exhausted();
+    }
+}
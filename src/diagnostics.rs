@@ -0,0 +1,70 @@
+use std::backtrace::Backtrace;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+/// An array currently rented from a pool, see `ArrayPool::outstanding_rentals`.
+#[derive(Clone, Debug)]
+pub struct Rental {
+    /// Capacity of the array.
+    pub capacity: usize,
+    /// Size of the array in bytes.
+    pub bytes: usize,
+    /// Thread that rented the array.
+    pub thread: ThreadId,
+    /// Where the array was rented.
+    pub rented_at: Arc<Backtrace>,
+    /// Where the array was allocated, if by this pool while capturing.
+    pub allocated_at: Option<Arc<Backtrace>>,
+}
+
+/// Keeps a backtrace of every outstanding rental and of the allocation of
+/// every array.
+pub(crate) struct RentalRegistry {
+    next_id: AtomicU64,
+    outstanding: Mutex<HashMap<u64, Rental>>,
+    /// Allocation backtraces keyed by the address of the array. A freed
+    /// array's entry stays until its address is allocated again.
+    origins: Mutex<HashMap<usize, Arc<Backtrace>>>,
+}
+
+impl RentalRegistry {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            outstanding: Mutex::new(HashMap::new()),
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record the rental of the array at `address`, allocated by this rent
+    /// if `allocated`, and return its id.
+    pub fn rent(&self, address: usize, capacity: usize, bytes: usize, allocated: bool) -> u64 {
+        let rented_at = Arc::new(Backtrace::force_capture());
+        let allocated_at = {
+            let mut origins = self.origins.lock().unwrap();
+            if allocated {
+                origins.insert(address, rented_at.clone());
+            }
+            origins.get(&address).cloned()
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let rental = Rental { capacity, bytes, thread: thread::current().id(), rented_at, allocated_at };
+        self.outstanding.lock().unwrap().insert(id, rental);
+        id
+    }
+
+    /// Forget the rental `id`, once its array is returned.
+    pub fn release(&self, id: u64) {
+        self.outstanding.lock().unwrap().remove(&id);
+    }
+
+    /// Gets every outstanding rental, largest first.
+    pub fn outstanding(&self) -> Vec<Rental> {
+        let mut rentals: Vec<Rental> = self.outstanding.lock().unwrap().values().cloned().collect();
+        rentals.sort_by_key(|rental| Reverse(rental.bytes));
+        rentals
+    }
+}
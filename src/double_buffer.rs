@@ -0,0 +1,58 @@
+use std::mem::swap;
+use std::ptr::drop_in_place;
+use std::sync::Arc;
+
+use crate::backend::{rent_default, PoolBackend};
+use crate::pool::{ArrayPool, ArrayPoolError};
+
+/// Two buffers of the same capacity, one being read while the other is
+/// written, swapped once the back buffer is complete.
+pub struct DoubleBuffer<T: Default, P: PoolBackend<T> = ArrayPool<T>> {
+    front: P::Buffer,
+    back: P::Buffer,
+}
+
+impl<T: Default, P: PoolBackend<T>> DoubleBuffer<T, P> {
+    /// Rent two buffers of at least `capacity` elements, each element is
+    /// initialized by the default constructor.
+    pub fn create(pool: &Arc<P>, capacity: usize) -> Result<Self, ArrayPoolError> {
+        Ok(Self {
+            front: rent_default(pool.as_ref(), capacity)?,
+            back: rent_default(pool.as_ref(), capacity)?,
+        })
+    }
+
+    /// Gets the buffer being read.
+    pub fn front(&self) -> &[T] {
+        &self.front
+    }
+
+    /// Gets the buffer being written.
+    pub fn back(&self) -> &[T] {
+        &self.back
+    }
+
+    /// Gets the buffer being written.
+    pub fn back_mut(&mut self) -> &mut [T] {
+        &mut self.back
+    }
+
+    /// Exchange the front and back buffers.
+    pub fn swap(&mut self) {
+        swap(&mut self.front, &mut self.back);
+    }
+
+    /// Gets the capacity of each buffer.
+    pub fn capacity(&self) -> usize {
+        self.front.len()
+    }
+}
+
+impl<T: Default, P: PoolBackend<T>> Drop for DoubleBuffer<T, P> {
+    fn drop(&mut self) {
+        // The pool won't drop the elements of buffers rented through `PoolBackend`
+        for elem in self.front.iter_mut().chain(self.back.iter_mut()) {
+            unsafe { drop_in_place(elem); }
+        }
+    }
+}
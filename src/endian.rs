@@ -0,0 +1,180 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::backend::PoolBackend;
+use crate::vec::PooledVec;
+
+macro_rules! put_methods {
+    ($($ty:ty => $le:ident $be:ident,)*) => {$(
+        #[doc = concat!("Write a little-endian `", stringify!($ty), "`.")]
+        fn $le(&mut self, value: $ty) {
+            self.put_slice(&value.to_le_bytes());
+        }
+
+        #[doc = concat!("Write a big-endian `", stringify!($ty), "`.")]
+        fn $be(&mut self, value: $ty) {
+            self.put_slice(&value.to_be_bytes());
+        }
+    )*};
+}
+macro_rules! get_methods {
+    ($($ty:ty => $le:ident $be:ident,)*) => {$(
+        #[doc = concat!("Read a little-endian `", stringify!($ty), "`, `None` if too few bytes remain.")]
+        pub fn $le(&mut self) -> Option<$ty> {
+            self.get_array().map(<$ty>::from_le_bytes)
+        }
+
+        #[doc = concat!("Read a big-endian `", stringify!($ty), "`, `None` if too few bytes remain.")]
+        pub fn $be(&mut self) -> Option<$ty> {
+            self.get_array().map(<$ty>::from_be_bytes)
+        }
+    )*};
+}
+
+/// Reads and writes integers at a tracked position of a byte buffer, such
+/// as a `BorrowingSlice<u8>` or a `PooledVec<u8>`.
+pub struct ByteCursor<B> {
+    buffer: B,
+    position: usize,
+}
+
+/// Appends integers to a byte buffer.
+pub trait PutBytes {
+    /// Write `bytes` after the previous writes.
+    fn put_slice(&mut self, bytes: &[u8]);
+
+    /// Write a byte.
+    fn put_u8(&mut self, value: u8) {
+        self.put_slice(&[value]);
+    }
+
+    /// Write `value` as an unsigned LEB128 varint, 1 to 10 bytes long.
+    fn put_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.put_u8(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.put_u8(value as u8);
+    }
+
+    /// Write `value` as a zigzag-encoded LEB128 varint, so small negative
+    /// numbers stay short.
+    fn put_varint_signed(&mut self, value: i64) {
+        self.put_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    put_methods! {
+        u16 => put_u16_le put_u16_be,
+        u32 => put_u32_le put_u32_be,
+        u64 => put_u64_le put_u64_be,
+        i16 => put_i16_le put_i16_be,
+        i32 => put_i32_le put_i32_be,
+        i64 => put_i64_le put_i64_be,
+        f32 => put_f32_le put_f32_be,
+        f64 => put_f64_le put_f64_be,
+    }
+}
+
+impl<B> ByteCursor<B> {
+    /// Create a new cursor at the start of `buffer`.
+    pub fn new(buffer: B) -> Self {
+        Self { buffer, position: 0 }
+    }
+
+    /// Gets the position of the next read or write.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Move the next read or write to `position`.
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Gets the buffer.
+    pub fn get_ref(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Take back the buffer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
+
+impl<B: Deref<Target = [u8]>> ByteCursor<B> {
+    /// Gets the number of bytes after the position.
+    pub fn remaining(&self) -> usize {
+        self.buffer.len().saturating_sub(self.position)
+    }
+
+    /// Read the next `len` bytes, `None` if too few bytes remain.
+    pub fn get_slice(&mut self, len: usize) -> Option<&[u8]> {
+        if self.remaining() < len { return None; }
+        let start = self.position;
+        self.position += len;
+        Some(&self.buffer[start..start + len])
+    }
+
+    fn get_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.get_slice(N).map(|bytes| bytes.try_into().unwrap())
+    }
+
+    /// Read a byte, `None` at the end of the buffer.
+    pub fn get_u8(&mut self) -> Option<u8> {
+        self.get_array().map(|[byte]| byte)
+    }
+
+    /// Read an unsigned LEB128 varint.
+    ///
+    /// Returns `None` without moving if the buffer ends inside the varint
+    /// or it doesn't fit in 64 bits.
+    pub fn get_varint(&mut self) -> Option<u64> {
+        let start = self.position;
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let Some(byte) = self.get_u8() else { break };
+            let bits = (byte & 0x7f) as u64;
+            if shift == 63 && bits > 1 { break; }
+            value |= bits << shift;
+            if byte < 0x80 { return Some(value); }
+        }
+        self.position = start;
+        None
+    }
+
+    /// Read a zigzag-encoded LEB128 varint, see `get_varint`.
+    pub fn get_varint_signed(&mut self) -> Option<i64> {
+        self.get_varint().map(|value| (value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    get_methods! {
+        u16 => get_u16_le get_u16_be,
+        u32 => get_u32_le get_u32_be,
+        u64 => get_u64_le get_u64_be,
+        i16 => get_i16_le get_i16_be,
+        i32 => get_i32_le get_i32_be,
+        i64 => get_i64_le get_i64_be,
+        f32 => get_f32_le get_f32_be,
+        f64 => get_f64_le get_f64_be,
+    }
+}
+
+impl<B: DerefMut<Target = [u8]>> PutBytes for ByteCursor<B> {
+    /// Overwrite the bytes at the position.
+    ///
+    /// Panics if fewer than `bytes.len()` bytes remain.
+    fn put_slice(&mut self, bytes: &[u8]) {
+        assert!(self.remaining() >= bytes.len(), "Not enough room left in buffer");
+        self.buffer[self.position..self.position + bytes.len()].copy_from_slice(bytes);
+        self.position += bytes.len();
+    }
+}
+
+impl<P: PoolBackend<u8>> PutBytes for PooledVec<u8, P> {
+    /// Append `bytes`, expanding the buffer if needed.
+    fn put_slice(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.push(*byte);
+        }
+    }
+}
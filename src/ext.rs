@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::vec::PooledVec;
+
+/// Move a collection into a `PooledVec`.
+pub trait IntoPooled<T> {
+    /// Move every element into a `PooledVec` renting a single buffer from `pool`.
+    fn into_pooled<P: PoolBackend<T>>(self, pool: &Arc<P>) -> PooledVec<T, P>;
+}
+
+/// Clone a slice into a `PooledVec`.
+pub trait ToPooled<T> {
+    /// Clone every element into a `PooledVec` renting a single buffer from `pool`.
+    fn to_pooled<P: PoolBackend<T>>(&self, pool: &Arc<P>) -> PooledVec<T, P>;
+}
+
+/// Collect an iterator into a `PooledVec`.
+pub trait IteratorExt: Iterator + Sized {
+    /// Collect every item into a `PooledVec` renting from `pool`.
+    ///
+    /// If the iterator reports an exact length, the final buffer is rented
+    /// up front, otherwise the vector starts with room for the lower bound.
+    fn collect_pooled<P: PoolBackend<Self::Item>>(self, pool: &Arc<P>) -> PooledVec<Self::Item, P> {
+        let capacity = match self.size_hint() {
+            (lower, Some(upper)) if lower == upper => lower,
+            (lower, _) => lower.min(pool.max_size()),
+        };
+        let mut vec = PooledVec::with_capacity(pool.clone(), capacity);
+        for x in self {
+            vec.push(x);
+        }
+        vec
+    }
+}
+
+impl<I: Iterator> IteratorExt for I {}
+
+impl<T> IntoPooled<T> for Vec<T> {
+    fn into_pooled<P: PoolBackend<T>>(self, pool: &Arc<P>) -> PooledVec<T, P> {
+        let mut vec = PooledVec::with_capacity(pool.clone(), self.len());
+        for x in self {
+            vec.push(x);
+        }
+        vec
+    }
+}
+
+impl<T: Clone> ToPooled<T> for [T] {
+    fn to_pooled<P: PoolBackend<T>>(&self, pool: &Arc<P>) -> PooledVec<T, P> {
+        let mut vec = PooledVec::with_capacity(pool.clone(), self.len());
+        for x in self {
+            vec.push(x.clone());
+        }
+        vec
+    }
+}
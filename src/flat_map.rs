@@ -0,0 +1,144 @@
+use std::borrow::Borrow;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+use crate::vec::PooledVec;
+
+/// A map kept as sorted keys and their values in two pooled vectors,
+/// looked up by binary search.
+///
+/// Suits small, read-heavy maps: lookups touch contiguous memory, while
+/// inserting and removing shift the entries after the position.
+pub struct PooledFlatMap<K, V, PK: PoolBackend<K> = ArrayPool<K>, PV: PoolBackend<V> = ArrayPool<V>> {
+    keys: PooledVec<K, PK>,
+    values: PooledVec<V, PV>,
+}
+
+impl<K: Ord, V, PK: PoolBackend<K>, PV: PoolBackend<V>> PooledFlatMap<K, V, PK, PV> {
+    /// Create a new map renting keys from `key_pool` and values from `value_pool`.
+    pub fn create(key_pool: Arc<PK>, value_pool: Arc<PV>) -> Self {
+        Self {
+            keys: PooledVec::create(key_pool),
+            values: PooledVec::create(value_pool),
+        }
+    }
+
+    /// Create a new map from `entries` sorted by key.
+    ///
+    /// Panics if the keys are not strictly increasing.
+    pub fn from_sorted<I: IntoIterator<Item = (K, V)>>(key_pool: Arc<PK>, value_pool: Arc<PV>, entries: I) -> Self {
+        let entries = entries.into_iter();
+        let capacity = entries.size_hint().0;
+        let mut map = Self {
+            keys: PooledVec::with_capacity(key_pool, capacity),
+            values: PooledVec::with_capacity(value_pool, capacity),
+        };
+        for (key, value) in entries {
+            if let Some(last) = map.keys.last() {
+                assert!(*last < key, "Keys must be sorted and unique");
+            }
+            map.keys.push(key);
+            map.values.push(value);
+        }
+        map
+    }
+
+    fn find<Q: Ord + ?Sized>(&self, key: &Q) -> Result<usize, usize> where K: Borrow<Q> {
+        self.keys.binary_search_by(|probe| probe.borrow().cmp(key))
+    }
+
+    /// Gets the number of entries.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check whether this map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Gets the value of `key`.
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<&V> where K: Borrow<Q> {
+        self.find(key).ok().map(|index| &self.values[index])
+    }
+
+    /// Gets a mutable reference to the value of `key`.
+    pub fn get_mut<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q> {
+        self.find(key).ok().map(|index| &mut self.values[index])
+    }
+
+    /// Check whether this map holds `key`.
+    pub fn contains_key<Q: Ord + ?Sized>(&self, key: &Q) -> bool where K: Borrow<Q> {
+        self.find(key).is_ok()
+    }
+
+    /// Insert `value` under `key` and return the value it replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.find(&key) {
+            Ok(index) => Some(std::mem::replace(&mut self.values[index], value)),
+            Err(index) => {
+                self.keys.insert(index, key);
+                self.values.insert(index, value);
+                None
+            }
+        }
+    }
+
+    /// Remove `key` and return its value.
+    pub fn remove<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q> {
+        let index = self.find(key).ok()?;
+        self.keys.remove(index);
+        Some(self.values.remove(index))
+    }
+
+    /// Remove every entry.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.values.clear();
+    }
+
+    /// Gets the keys in ascending order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Gets the values in the order of their keys.
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Gets mutable references to the values in the order of their keys.
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Iterate over the entries in ascending key order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    /// Iterate over the entries whose key is in `range`, in ascending order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.keys.partition_point(|probe| probe < key),
+            Bound::Excluded(key) => self.keys.partition_point(|probe| probe <= key),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => self.keys.partition_point(|probe| probe <= key),
+            Bound::Excluded(key) => self.keys.partition_point(|probe| probe < key),
+            Bound::Unbounded => self.keys.len(),
+        }.max(start);
+        self.keys[start..end].iter().zip(self.values[start..end].iter())
+    }
+}
+
+impl<K, V, PK: PoolBackend<K>, PV: PoolBackend<V>> Drop for PooledFlatMap<K, V, PK, PV> {
+    fn drop(&mut self) {
+        // Pooled vectors don't drop their elements
+        self.keys.clear();
+        self.values.clear();
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::{ArrayPool, ArrayPoolError};
+
+/// A sequence with a gap of free slots at its cursor, over a pooled buffer.
+///
+/// Inserting and deleting at the cursor is amortized O(1), moving the
+/// cursor shifts the elements in between across the gap. Growing rents a
+/// buffer of the next size class, twice as large at least. Suits text
+/// edited around a caret, as bytes or `char`s.
+pub struct PooledGapBuffer<T: Copy, P: PoolBackend<T> = ArrayPool<T>> {
+    pool: Arc<P>,
+    buffer: Option<P::Buffer>,
+    gap_start: usize,
+    gap_end: usize,
+}
+
+impl<T: Copy, P: PoolBackend<T>> PooledGapBuffer<T, P> {
+    /// Create a new, empty gap buffer.
+    pub fn create(pool: Arc<P>) -> Self {
+        Self {
+            pool,
+            buffer: None,
+            gap_start: 0,
+            gap_end: 0,
+        }
+    }
+
+    fn elements(&mut self) -> *mut T {
+        match &mut self.buffer {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => std::ptr::NonNull::dangling().as_ptr(),
+        }
+    }
+
+    /// Gets the number of elements.
+    pub fn len(&self) -> usize {
+        self.capacity() - (self.gap_end - self.gap_start)
+    }
+
+    /// Check whether this gap buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the capacity of the underlying buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.as_ref().map_or(0, |buffer| buffer.len())
+    }
+
+    /// Gets the number of elements before the cursor.
+    pub fn cursor(&self) -> usize {
+        self.gap_start
+    }
+
+    /// Move the cursor before the element at `index`.
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn move_to(&mut self, index: usize) {
+        assert!(index <= self.len(), "Cursor index out of bound");
+        let (gap_start, gap_end) = (self.gap_start, self.gap_end);
+        let elements = self.elements();
+        unsafe {
+            if index < gap_start {
+                let moved = gap_start - index;
+                std::ptr::copy(elements.add(index), elements.add(gap_end - moved), moved);
+                self.gap_end -= moved;
+            } else {
+                let moved = index - gap_start;
+                std::ptr::copy(elements.add(gap_end), elements.add(gap_start), moved);
+                self.gap_end += moved;
+            }
+        }
+        self.gap_start = index;
+    }
+
+    /// Move the cursor back by one element, `false` at the start.
+    pub fn move_left(&mut self) -> bool {
+        if self.gap_start == 0 { return false; }
+        self.move_to(self.gap_start - 1);
+        true
+    }
+
+    /// Move the cursor forward by one element, `false` at the end.
+    pub fn move_right(&mut self) -> bool {
+        if self.gap_start == self.len() { return false; }
+        self.move_to(self.gap_start + 1);
+        true
+    }
+
+    /// Make room for at least `additional` more elements in the gap.
+    ///
+    /// Fails without touching the gap buffer if the pool can't supply a
+    /// big enough buffer.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ArrayPoolError> {
+        if self.gap_end - self.gap_start >= additional { return Ok(()); }
+        let capacity = self.capacity();
+        let required = self.len().checked_add(additional).ok_or(ArrayPoolError::MaxChunkSizeNotSufficient)?;
+        let mut new_buffer = unsafe { self.pool.rent_uninitialized(required.max(capacity * 2)) }?;
+        let back = capacity - self.gap_end;
+        let new_gap_end = new_buffer.len() - back;
+        let gap_start = self.gap_start;
+        let gap_end = self.gap_end;
+        let elements = self.elements();
+        unsafe {
+            std::ptr::copy_nonoverlapping(elements, new_buffer.as_mut_ptr(), gap_start);
+            std::ptr::copy_nonoverlapping(elements.add(gap_end), new_buffer.as_mut_ptr().add(new_gap_end), back);
+        }
+        if let Some(old_buffer) = self.buffer.replace(new_buffer) {
+            self.pool.return_buffer(old_buffer);
+        }
+        self.gap_end = new_gap_end;
+        Ok(())
+    }
+
+    /// Insert `value` before the cursor, the cursor moves past it.
+    pub fn insert(&mut self, value: T) {
+        self.insert_slice(&[value]);
+    }
+
+    /// Insert `values` before the cursor, the cursor moves past them.
+    pub fn insert_slice(&mut self, values: &[T]) {
+        self.try_reserve(values.len()).expect("Could not request buffer");
+        let gap_start = self.gap_start;
+        let elements = self.elements();
+        unsafe { std::ptr::copy_nonoverlapping(values.as_ptr(), elements.add(gap_start), values.len()); }
+        self.gap_start += values.len();
+    }
+
+    /// Delete up to `count` elements before the cursor, like a backspace,
+    /// and return how many were deleted.
+    pub fn delete_before(&mut self, count: usize) -> usize {
+        let count = count.min(self.gap_start);
+        self.gap_start -= count;
+        count
+    }
+
+    /// Delete up to `count` elements after the cursor and return how many
+    /// were deleted.
+    pub fn delete_after(&mut self, count: usize) -> usize {
+        let count = count.min(self.capacity() - self.gap_end);
+        self.gap_end += count;
+        count
+    }
+
+    /// Remove every element and return the buffer to the pool.
+    pub fn clear(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.return_buffer(buffer);
+        }
+        self.gap_start = 0;
+        self.gap_end = 0;
+    }
+
+    /// Gets the elements before and after the cursor.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        match &self.buffer {
+            Some(buffer) => (&buffer[..self.gap_start], &buffer[self.gap_end..]),
+            None => (&[], &[]),
+        }
+    }
+
+    /// Gets the element at `index`, may return `None` if index is out of bound.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let (front, back) = self.as_slices();
+        match index.checked_sub(front.len()) {
+            None => front.get(index),
+            Some(index) => back.get(index),
+        }
+    }
+
+    /// Iterate over the elements in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        let (front, back) = self.as_slices();
+        front.iter().chain(back)
+    }
+}
+
+impl<P: PoolBackend<u8>> PooledGapBuffer<u8, P> {
+    /// Insert the UTF-8 bytes of `string` before the cursor.
+    pub fn insert_str(&mut self, string: &str) {
+        self.insert_slice(string.as_bytes());
+    }
+}
+
+impl<T: Copy, P: PoolBackend<T>> Drop for PooledGapBuffer<T, P> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
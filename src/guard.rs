@@ -0,0 +1,64 @@
+use std::mem::{forget, needs_drop};
+use std::ptr::drop_in_place;
+
+/// Fills uninitialized slots one at a time, dropping the filled ones if
+/// dropped before `finish`, so a panicking constructor neither leaks nor
+/// double-drops elements.
+pub(crate) struct InitGuard<'a, T> {
+    slots: &'a mut [T],
+    initialized: usize,
+}
+
+impl<'a, T> InitGuard<'a, T> {
+    /// # Safety
+    ///
+    /// `slots` must be uninitialized, their content is never dropped.
+    pub unsafe fn new(slots: &'a mut [T]) -> Self {
+        Self { slots, initialized: 0 }
+    }
+
+    /// Write `value` into the next slot.
+    pub fn push(&mut self, value: T) {
+        // Slots past `initialized` are uninitialized, avoid dropping them
+        unsafe { std::ptr::write(&mut self.slots[self.initialized], value); }
+        self.initialized += 1;
+    }
+
+    /// Keep the filled slots and return how many there are.
+    pub fn finish(self) -> usize {
+        let initialized = self.initialized;
+        forget(self);
+        initialized
+    }
+}
+
+impl<T> Drop for InitGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe { drop_in_place(&mut self.slots[..self.initialized]); }
+    }
+}
+
+/// Fill uninitialized `slots` with clones of `value`.
+///
+/// Types without drop glue are filled with `<[T]>::fill`, which copies
+/// `Copy` values without calling `clone`, a single memset for bytes.
+///
+/// # Safety
+///
+/// `slots` must be uninitialized, their content is never dropped.
+pub(crate) unsafe fn fill_uninitialized<T: Clone>(slots: &mut [T], value: T) {
+    if !needs_drop::<T>() {
+        // Assigning never drops the previous content of these types
+        slots.fill(value);
+        return;
+    }
+    let len = slots.len();
+    let mut guard = InitGuard::new(slots);
+    for _ in 1..len {
+        guard.push(value.clone());
+    }
+    if len > 0 {
+        guard.push(value);
+    }
+    guard.finish();
+}
@@ -0,0 +1,50 @@
+use std::fmt::{Display, Formatter};
+
+use crate::backend::PoolBackend;
+use crate::pool::BorrowingSlice;
+use crate::vec::PooledVec;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Displays bytes in the format of `hexdump -C`: an offset, 16 bytes in
+/// hex and the same bytes as ASCII on each line.
+#[derive(Copy, Clone, Debug)]
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl Display for HexDump<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (line, bytes) in self.0.chunks(BYTES_PER_LINE).enumerate() {
+            write!(f, "{:08x} ", line * BYTES_PER_LINE)?;
+            for column in 0..BYTES_PER_LINE {
+                if column % 8 == 0 {
+                    write!(f, " ")?;
+                }
+                match bytes.get(column) {
+                    Some(byte) => write!(f, "{byte:02x} ")?,
+                    None => write!(f, "   ")?,
+                }
+            }
+            write!(f, " |")?;
+            for byte in bytes {
+                let printable = byte.is_ascii_graphic() || *byte == b' ';
+                write!(f, "{}", if printable { *byte as char } else { '.' })?;
+            }
+            writeln!(f, "|")?;
+        }
+        write!(f, "{:08x}", self.0.len())
+    }
+}
+
+impl BorrowingSlice<u8> {
+    /// Display the bytes of this array as a hexdump, see `HexDump`.
+    pub fn hexdump(&self) -> HexDump<'_> {
+        HexDump(self)
+    }
+}
+
+impl<P: PoolBackend<u8>> PooledVec<u8, P> {
+    /// Display the elements of this vector as a hexdump, see `HexDump`.
+    pub fn hexdump(&self) -> HexDump<'_> {
+        HexDump(self)
+    }
+}
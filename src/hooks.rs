@@ -0,0 +1,171 @@
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPoolError;
+
+/// Callbacks invoked when a buffer leaves or comes back to a pool,
+/// installed with `PoolBackend::layer`.
+///
+/// The elements of the buffer handed to a hook may be uninitialized: a hook
+/// can overwrite them (e.g. with `ptr::write_bytes` to scrub a buffer of plain
+/// data) but must not read or drop them.
+pub trait PoolHooks<T> {
+    /// Called after `buffer` has been rented, before it is handed out.
+    fn on_rent(&self, buffer: &mut [T]) {
+        let _ = buffer;
+    }
+
+    /// Called when `buffer` is returned, before it goes back to the pool.
+    fn on_return(&self, buffer: &mut [T]) {
+        let _ = buffer;
+    }
+}
+
+/// A pool wrapper that runs `H` on every buffer rented from and returned to `P`.
+///
+/// Expanding or shrinking a buffer rents the new buffer and returns the old
+/// one through this wrapper, so both hooks see every buffer changing hands.
+pub struct Layered<P, H> {
+    inner: P,
+    hooks: Arc<H>
+}
+
+/// A buffer rented from a `Layered` pool.
+pub struct HookedBuffer<T, B: DerefMut<Target = [T]>, H: PoolHooks<T>> {
+    buffer: B,
+    hooks: Arc<H>,
+    /// Carried over to the buffers this one is resized into.
+    tag: Option<&'static str>,
+    _marker: PhantomData<fn() -> T>
+}
+
+impl<T, B: DerefMut<Target = [T]>, H: PoolHooks<T>> HookedBuffer<T, B, H> {
+    fn into_inner(self) -> B {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            drop(ptr::read(&this.hooks));
+            ptr::read(&this.buffer)
+        }
+    }
+}
+
+impl<T, B: DerefMut<Target = [T]>, H: PoolHooks<T>> Drop for HookedBuffer<T, B, H> {
+    fn drop(&mut self) {
+        self.hooks.on_return(&mut self.buffer);
+    }
+}
+
+impl<T, B: DerefMut<Target = [T]>, H: PoolHooks<T>> Deref for HookedBuffer<T, B, H> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.deref()
+    }
+}
+
+impl<T, B: DerefMut<Target = [T]>, H: PoolHooks<T>> DerefMut for HookedBuffer<T, B, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer.deref_mut()
+    }
+}
+
+impl<P, H> Layered<P, H> {
+    /// Wrap `inner`, running `hooks` on every buffer going through it.
+    pub fn new(inner: P, hooks: H) -> Self {
+        Self {
+            inner,
+            hooks: Arc::new(hooks),
+        }
+    }
+
+    /// Gets the wrapped pool.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Gets the installed hooks.
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    fn wrap<T, B: DerefMut<Target = [T]>>(&self, mut buffer: B) -> HookedBuffer<T, B, H>
+    where H: PoolHooks<T> {
+        self.hooks.on_rent(&mut buffer);
+        HookedBuffer {
+            buffer,
+            hooks: self.hooks.clone(),
+            tag: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Move the first `count` elements of `buffer` into `resized`, a buffer
+    /// rented from the inner pool, then return `buffer`.
+    unsafe fn move_into<T>(&self, buffer: HookedBuffer<T, P::Buffer, H>, mut resized: P::Buffer, count: usize) -> HookedBuffer<T, P::Buffer, H>
+    where P: PoolBackend<T>, H: PoolHooks<T> {
+        if let Some(tag) = buffer.tag {
+            self.inner.tag_buffer(&mut resized, tag);
+        }
+        let mut resized = self.wrap(resized);
+        resized.tag = buffer.tag;
+        // A bitwise move leaves the old buffer logically uninitialized
+        ptr::copy_nonoverlapping(buffer.as_ptr(), resized.as_mut_ptr(), count);
+        self.return_buffer(buffer);
+        resized
+    }
+}
+
+impl<T, P: PoolBackend<T>, H: PoolHooks<T>> PoolBackend<T> for Layered<P, H> {
+    type Buffer = HookedBuffer<T, P::Buffer, H>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.inner.rent_uninitialized(minimum_capacity).map(|buffer| self.wrap(buffer))
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.inner.rent_minimum_uninitialized().map(|buffer| self.wrap(buffer))
+    }
+
+    fn return_buffer(&self, buffer: Self::Buffer) {
+        let mut buffer = buffer.into_inner();
+        self.hooks.on_return(&mut buffer);
+        self.inner.return_buffer(buffer);
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        let len = buffer.len();
+        let expanded = self.inner.rent_uninitialized(len * 2)?;
+        Ok(self.move_into(buffer, expanded, len))
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        let new_size = buffer.len() / 2;
+        if new_size < self.inner.min_size() { return buffer; }
+        match self.inner.rent_uninitialized(new_size) {
+            Ok(shrunk) if shrunk.len() < buffer.len() => self.move_into(buffer, shrunk, new_size),
+            // Never handed out, so no hook has seen it
+            Ok(unused) => {
+                self.inner.return_buffer(unused);
+                buffer
+            }
+            Err(_) => buffer,
+        }
+    }
+
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        buffer.tag = Some(tag);
+        self.inner.tag_buffer(&mut buffer.buffer, tag)
+    }
+
+    fn min_size(&self) -> usize {
+        self.inner.min_size()
+    }
+
+    fn max_size(&self) -> usize {
+        self.inner.max_size()
+    }
+}
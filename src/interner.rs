@@ -0,0 +1,120 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::str;
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::{ArrayPool, ArrayPoolError};
+
+const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+/// Identifies a string interned by a `PooledInterner`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Gets the index of this symbol, symbols are numbered in interning order.
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+struct Span {
+    chunk: usize,
+    start: usize,
+    len: usize,
+}
+
+/// Interns strings into byte chunks rented from a pool.
+///
+/// Interned strings never move, every chunk returns to the pool when the
+/// interner is dropped.
+pub struct PooledInterner<P: PoolBackend<u8> = ArrayPool<u8>> {
+    pool: Arc<P>,
+    chunk_size: usize,
+    chunks: Vec<P::Buffer>,
+    used: usize,
+    spans: Vec<Span>,
+    lookup: HashMap<u64, Vec<Symbol>>,
+}
+
+fn hash_of(string: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    string.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<P: PoolBackend<u8>> PooledInterner<P> {
+    /// Create a new interner renting chunks of 4 KiB, or the largest
+    /// capacity of `pool` if smaller.
+    pub fn create(pool: Arc<P>) -> Self {
+        let chunk_size = DEFAULT_CHUNK_SIZE.min(pool.max_size());
+        Self::with_chunk_size(pool, chunk_size)
+    }
+
+    /// Create a new interner renting chunks of at least `chunk_size` bytes.
+    pub fn with_chunk_size(pool: Arc<P>, chunk_size: usize) -> Self {
+        Self {
+            pool,
+            chunk_size,
+            chunks: vec![],
+            used: 0,
+            spans: vec![],
+            lookup: HashMap::new(),
+        }
+    }
+
+    /// Gets the symbol of `string` if it was interned.
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.lookup.get(&hash_of(string))?
+            .iter()
+            .find(|symbol| self.resolve(**symbol) == string)
+            .copied()
+    }
+
+    /// Intern `string`, returning its symbol.
+    ///
+    /// Fails if a chunk large enough for `string` can't be rented.
+    pub fn intern(&mut self, string: &str) -> Result<Symbol, ArrayPoolError> {
+        if let Some(symbol) = self.get(string) {
+            return Ok(symbol);
+        }
+        let bytes = string.as_bytes();
+        let fits = self.chunks.last().is_some_and(|chunk| chunk.len() - self.used >= bytes.len());
+        if !fits {
+            let chunk = unsafe { self.pool.rent_uninitialized(self.chunk_size.max(bytes.len())) }?;
+            self.chunks.push(chunk);
+            self.used = 0;
+        }
+        let chunk = self.chunks.len() - 1;
+        let start = self.used;
+        self.chunks[chunk][start..start + bytes.len()].copy_from_slice(bytes);
+        self.used += bytes.len();
+
+        let symbol = Symbol(self.spans.len() as u32);
+        self.spans.push(Span { chunk, start, len: bytes.len() });
+        self.lookup.entry(hash_of(string)).or_default().push(symbol);
+        Ok(symbol)
+    }
+
+    /// Gets the string of `symbol`.
+    ///
+    /// Panics if `symbol` was not returned by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        let span = &self.spans[symbol.index()];
+        let bytes = &self.chunks[span.chunk][span.start..span.start + span.len];
+        // Only valid UTF-8 was copied into this span
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+
+    /// Gets the number of interned strings.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Check whether no string was interned.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
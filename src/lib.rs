@@ -1,23 +1,85 @@
+#![cfg_attr(feature = "read-buf", feature(core_io_borrowed_buf, read_buf))]
+
 pub mod pool;
 pub mod vec;
+pub mod stats;
+pub mod manager;
+pub mod backend;
+pub mod testing;
+pub mod local;
+pub mod hooks;
+pub mod spill;
+pub mod config;
+pub mod align;
+pub mod ext;
+pub mod interner;
+pub mod lru;
+pub mod recycler;
+pub mod double_buffer;
+pub mod realtime;
+pub mod allocator;
+pub mod endian;
+pub mod hexdump;
+pub mod static_pool;
+pub mod read_buf;
+pub mod profiler;
+pub mod passthrough;
+pub mod packet;
+pub mod net;
+pub mod flat_map;
+pub mod gap_buffer;
+pub mod lines;
+pub mod shared;
+pub mod append_log;
+pub mod cursor;
+pub mod pool_set;
+pub mod slot_map;
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(feature = "backtrace")]
+pub mod diagnostics;
 pub(crate) mod raw_buffer;
+pub(crate) mod guard;
 
 #[cfg(test)]
 mod tests {
     use std::ops::{Deref};
     use std::rc::Rc;
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::thread;
-    use lazy_static::lazy_static;
-    use crate::pool::ArrayPool;
+    use crate::pool::{ArrayPool, ArrayPoolError, Sharding};
     use crate::vec::PooledVec;
+    use crate::manager::PoolManager;
+    use crate::backend::PoolBackend;
+    use crate::testing::CountingPool;
+    use crate::local::LocalArrayPool;
+    use crate::hooks::PoolHooks;
+    use crate::spill::SpillVec;
+    use crate::config::PoolConfig;
+    use crate::align::{AlignedPool, Align64};
+    use crate::ext::{IntoPooled, IteratorExt, ToPooled};
+    use crate::interner::PooledInterner;
+    use crate::lru::{LruCapacity, PooledLruCache};
+    use crate::recycler::{BufferRecycler, RecycledBuffer};
+    use crate::double_buffer::DoubleBuffer;
+    use crate::realtime::RealtimePool;
+    use crate::allocator::BufferAllocator;
+    use crate::endian::{ByteCursor, PutBytes};
+    use crate::static_pool::StaticPool;
+    use crate::read_buf::PooledReadBuf;
+    use crate::passthrough::{EitherPool, PassthroughPool};
+    use crate::packet::PacketPool;
+    use crate::flat_map::PooledFlatMap;
+    use crate::gap_buffer::PooledGapBuffer;
+    use crate::stats::Compaction;
+    use crate::lines::PooledLines;
+    use crate::pool_set::{Affinity, LeastLoaded, PoolSet, RoundRobin};
+    use crate::slot_map::PooledSlotMap;
+    #[cfg(feature = "bytemuck")]
+    use crate::align::Align16;
 
-    lazy_static!{
-        static ref POOL: Arc<ArrayPool<u32>> = {
-            Arc::new(ArrayPool::new())
-        };
-    }
+    static POOL: StaticPool<u32> = StaticPool::new();
 
     struct DropTestStruct(Rc<AtomicUsize>);
 
@@ -50,6 +112,32 @@ mod tests {
         assert_eq!(counter_2.load(Ordering::Relaxed), 0);
     }
 
+    #[test]
+    fn rent_with_cached_test(){
+        struct Live(Arc<AtomicUsize>);
+
+        impl Drop for Live {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        let live = Arc::new(AtomicUsize::new(0));
+        let pool = ArrayPool::<Live>::with_max_power(6).unwrap();
+        let mut made = 0usize;
+        for _ in 0..3 {
+            let slice = pool.rent_with(10, &mut || {
+                made += 1;
+                live.fetch_add(1, Ordering::SeqCst);
+                Live(live.clone())
+            }).unwrap();
+            assert_eq!(live.load(Ordering::SeqCst), slice.len());
+        }
+        // Cached arrays are filled again instead of handing out dropped elements
+        assert_eq!((made, pool.stats().allocations), (48, 1));
+        assert_eq!(live.load(Ordering::SeqCst), 0);
+    }
+
     fn simple_pool_test(pool: &ArrayPool<u32>) {
         let mut borrowed = unsafe { pool.rent_or_create_uninitialized(3, false).unwrap() };
         borrowed[1] = 1;
@@ -127,4 +215,1795 @@ mod tests {
     fn test_vec(){
         test_vec_internal()
     }
+
+    #[test]
+    fn manager_test(){
+        let manager = PoolManager::with_max_power(8).unwrap();
+        let ints = manager.pool::<u32>();
+        assert!(Arc::ptr_eq(&ints, &manager.pool::<u32>()));
+        assert_eq!(manager.pool::<u8>().max_size(), 128);
+        assert_eq!(manager.len(), 2);
+        {
+            let _ints = ints.rent(10).unwrap();
+            let _bytes = manager.pool::<u8>().rent(10).unwrap();
+        }
+        let stats = manager.stats();
+        assert_eq!(stats.rents, 2);
+        assert_eq!(stats.returns, 2);
+        assert_eq!(stats.allocations, 2);
+        assert_eq!(stats.cached, 2);
+        assert_eq!(stats.cached_bytes, 16 * 4 + 16);
+
+        let custom = Arc::new(ArrayPool::<u32>::with_max_power(4).unwrap());
+        manager.set_pool(custom.clone());
+        assert!(Arc::ptr_eq(&custom, &manager.pool::<u32>()));
+        assert_eq!(manager.len(), 2);
+    }
+
+    fn fill_vec<P: PoolBackend<u32>>(pool: Arc<P>, count: u32) -> PooledVec<u32, P> {
+        let mut vec = PooledVec::create(pool);
+        for x in 0..count {
+            vec.push(x);
+        }
+        vec
+    }
+
+    #[test]
+    fn backend_test(){
+        let pool: Arc<ArrayPool<u32>> = Arc::new(ArrayPool::with_max_power(8).unwrap());
+        let vec = fill_vec(pool.clone(), 20);
+        assert_eq!(vec.capacity(), 32);
+        let cloned = vec.clone();
+        assert_eq!(&cloned[..], &vec[..]);
+        drop(vec);
+        drop(cloned);
+        let stats = pool.stats();
+        assert_eq!(stats.rents, stats.returns);
+    }
+
+    #[test]
+    fn counting_pool_test(){
+        let pool = Arc::new(CountingPool::new(ArrayPool::<u32>::with_max_power(8).unwrap()));
+        let mut vec = fill_vec(pool.clone(), 5);
+        assert_eq!(pool.rents(), 1);
+        pool.assert_no_rents(|| {
+            vec.push(5);
+            vec.pop();
+        });
+        vec.push(6);
+        vec.push(7);
+        vec.push(8);
+        vec.push(9);
+        assert_eq!(pool.rents(), 2);
+        assert_eq!(pool.outstanding(), 1);
+
+        pool.fail_next_rents(1);
+        assert!(unsafe { pool.rent_uninitialized(4) }.is_err());
+        assert!(unsafe { pool.rent_uninitialized(4) }.is_ok());
+        assert_eq!(pool.failures(), 1);
+        drop(vec);
+        assert_eq!(pool.outstanding(), 0);
+
+        // Cached arrays can be rented without allocating
+        let rents = pool.rents();
+        drop(pool.assert_no_allocations(|| fill_vec(pool.clone(), 5)));
+        assert_eq!(pool.rents(), rents + 1);
+        assert!(std::panic::catch_unwind(|| pool.assert_no_allocations(|| fill_vec(pool.clone(), 100))).is_err());
+
+        // Rents the inner pool fails are not counted
+        let rents = pool.rents();
+        assert!(unsafe { pool.rent_uninitialized(1024) }.is_err());
+        let largest = unsafe { pool.rent_uninitialized(128) }.unwrap();
+        assert!(unsafe { pool.expand_buffer(largest) }.is_err());
+        assert_eq!((pool.rents(), pool.outstanding()), (rents + 1, 0));
+    }
+
+    #[test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn local_pool_test(){
+        // PooledVec holds its pool through an Arc, even when it's never shared
+        let pool = Arc::new(LocalArrayPool::<u32>::with_max_power(8).unwrap());
+        {
+            let mut slice = pool.rent(11).unwrap();
+            assert_eq!(slice.len(), 16);
+            slice[11] = 11;
+        }
+        let vec = fill_vec(pool.clone(), 20);
+        assert_eq!(vec.capacity(), 32);
+        drop(vec);
+        let stats = pool.stats();
+        assert_eq!(stats.rents, stats.returns);
+        assert_eq!(stats.cached, stats.allocations);
+    }
+
+    #[test]
+    fn per_cpu_sharding_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_sharding(8, Sharding::PerCpu).unwrap());
+        for _ in 0..16 {
+            let cloned = pool.clone();
+            thread::spawn(move || {
+                let mut slice = cloned.rent(10).unwrap();
+                slice[10] = 10;
+            }).join().unwrap();
+        }
+        // Short-lived threads share the per-core chains instead of building their own
+        let stats = pool.stats();
+        assert_eq!(stats.rents, 16);
+        assert_eq!(stats.returns, 16);
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.cached, 1);
+    }
+
+    #[test]
+    fn hooks_test(){
+        #[derive(Default)]
+        struct Scrub {
+            rents: AtomicUsize,
+            returns: AtomicUsize,
+        }
+
+        impl PoolHooks<u32> for Scrub {
+            fn on_rent(&self, _buffer: &mut [u32]) {
+                self.rents.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn on_return(&self, buffer: &mut [u32]) {
+                self.returns.fetch_add(1, Ordering::SeqCst);
+                buffer.fill(0);
+            }
+        }
+
+        let inner = ArrayPool::<u32>::with_max_power(8).unwrap();
+        let pool = Arc::new(inner.layer(Scrub::default()));
+        let vec = fill_vec(pool.clone(), 20);
+        assert_eq!(vec.capacity(), 32);
+        assert!(vec.iter().copied().eq(0..20));
+        drop(vec);
+        // Growing through 8 and 16 rents and returns each buffer
+        assert_eq!(pool.hooks().rents.load(Ordering::SeqCst), 3);
+        assert_eq!(pool.hooks().returns.load(Ordering::SeqCst), 3);
+
+        // The scrubbed buffer is the one handed out next
+        let slice = unsafe { pool.inner().rent_or_create_uninitialized(32, false) }.unwrap();
+        assert!(slice.iter().all(|x| *x == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "stats-timing")]
+    fn steal_timing_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_max_power(8).unwrap());
+        // Own a chain before the other thread caches its array
+        let _held = pool.rent(10).unwrap();
+        let cached = std::sync::Barrier::new(2);
+        let stolen = std::sync::Barrier::new(2);
+        let _stolen = thread::scope(|scope| {
+            scope.spawn(|| {
+                pool.rent(10).unwrap();
+                cached.wait();
+                // Stay alive so the cached array isn't reclaimed
+                stolen.wait();
+            });
+            cached.wait();
+            let slice = pool.rent(10).unwrap();
+            stolen.wait();
+            slice
+        });
+        let stats = pool.stats();
+        assert_eq!(stats.steals, 1);
+        assert!(stats.steal_time > std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn debug_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_max_power(8).unwrap());
+        let vec = fill_vec(pool.clone(), 3);
+        assert_eq!(format!("{vec:?}"), "PooledVec { len: 3, capacity: 8, items: [0, 1, 2] }");
+        assert_eq!(
+            format!("{pool:?}"),
+            "ArrayPool { min_size: 8, max_size: 128, size_classes: 5, sharding: PerThread, \
+            classes: {8: BufferChain { chunk_size: 8, cached: 0, rents: 1, returns: 0, allocations: 1, steals: 0 }} }"
+        );
+    }
+
+    #[test]
+    fn spill_test(){
+        let pool = Arc::new(ArrayPool::<u64>::with_max_power(8).unwrap());
+        let mut vec = SpillVec::with_threshold(pool.clone(), 8);
+        for x in 0..20u64 {
+            vec.push(x).unwrap();
+        }
+        assert_eq!(vec.len(), 20);
+        assert_eq!(vec.spilled_len(), 16);
+        let items: Vec<u64> = vec.iter().unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(vec.iter().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn discard_test(){
+        let pool = ArrayPool::<u32>::with_max_power(8).unwrap();
+        pool.set_discard_threshold(Some(64 * 4));
+        drop(pool.rent(10).unwrap());
+        drop(pool.rent(100).unwrap());
+        let stats = pool.stats();
+        assert_eq!(stats.returns, 2);
+        assert_eq!(stats.discards, 1);
+        assert_eq!(stats.cached, 1);
+        assert_eq!(stats.cached_bytes, 16 * 4);
+
+        pool.set_discard_threshold(None);
+        drop(pool.rent(100).unwrap());
+        assert_eq!(pool.stats().cached, 2);
+    }
+
+    #[test]
+    fn profile_test(){
+        let pool = ArrayPool::<u32>::with_max_power(8).unwrap();
+        {
+            let _small = pool.rent(10).unwrap();
+            let _other = pool.rent(10).unwrap();
+            let _large = pool.rent(100).unwrap();
+        }
+        let mut profile = Vec::new();
+        pool.save_profile(&mut profile).unwrap();
+        assert_eq!(String::from_utf8(profile.clone()).unwrap(), "array_pool profile v1\n16 2\n128 1\n");
+
+        let warm = ArrayPool::<u32>::with_max_power(8).unwrap();
+        warm.prewarm_from_profile(&profile[..]).unwrap();
+        assert_eq!(warm.stats().cached, 3);
+        drop(warm.rent(100).unwrap());
+        assert_eq!(warm.stats().allocations, 3);
+
+        assert!(warm.prewarm_from_profile(&b"16 2\n"[..]).is_err());
+
+        // Arrays allocated again after an eviction are not more demand
+        warm.set_max_cached_bytes(Some(0));
+        warm.set_max_cached_bytes(None);
+        drop((0..2).map(|_| warm.rent(10).unwrap()).collect::<Vec<_>>());
+        let mut profile = Vec::new();
+        warm.save_profile(&mut profile).unwrap();
+        assert_eq!(String::from_utf8(profile).unwrap(), "array_pool profile v1\n16 2\n128 1\n");
+
+        let _held = warm.rent(100).unwrap();
+        warm.reset_stats();
+        let mut profile = Vec::new();
+        warm.save_profile(&mut profile).unwrap();
+        assert_eq!(String::from_utf8(profile).unwrap(), "array_pool profile v1\n128 1\n");
+    }
+
+    #[test]
+    fn config_test(){
+        std::env::set_var("CONFIG_TEST_POOL_MAX_POWER", "8");
+        std::env::set_var("CONFIG_TEST_POOL_MAX_BYTES", "128");
+        std::env::set_var("CONFIG_TEST_POOL_SCRUB", "true");
+        let config = PoolConfig::from_env("CONFIG_TEST_POOL").unwrap();
+        assert_eq!(config, PoolConfig {
+            max_power: 8,
+            max_cached_bytes: Some(128),
+            scrub: true,
+            ..PoolConfig::default()
+        });
+
+        let pool = ArrayPool::<u32>::from_config(config).unwrap();
+        assert_eq!(pool.max_size(), 128);
+        {
+            let mut first = pool.rent(16).unwrap();
+            first[3] = 3;
+            let _second = pool.rent(16).unwrap();
+            let _third = pool.rent(16).unwrap();
+        }
+        // Only two 64 bytes arrays fit in the budget
+        let stats = pool.stats();
+        assert_eq!((stats.cached, stats.discards), (2, 1));
+        let scrubbed = unsafe { pool.rent_or_create_uninitialized(16, false) }.unwrap();
+        assert!(scrubbed.iter().all(|x| *x == 0));
+
+        std::env::set_var("CONFIG_TEST_POOL_SHARDING", "sideways");
+        let error = PoolConfig::from_env("CONFIG_TEST_POOL").unwrap_err();
+        assert_eq!(error.variable, "CONFIG_TEST_POOL_SHARDING");
+    }
+
+    #[test]
+    fn reconfigure_test(){
+        let pool = ArrayPool::<u32>::with_max_power(8).unwrap();
+        {
+            let _small = pool.rent(10).unwrap();
+            let _other = pool.rent(10).unwrap();
+            let _large = pool.rent(100).unwrap();
+        }
+        assert_eq!(pool.stats().cached_bytes, 2 * 64 + 512);
+
+        // The large array goes first
+        pool.set_max_cached_bytes(Some(128));
+        let stats = pool.stats();
+        assert_eq!((stats.cached, stats.evictions), (2, 1));
+
+        pool.set_discard_threshold(Some(64));
+        let stats = pool.stats();
+        assert_eq!((stats.cached, stats.evictions), (0, 3));
+
+        pool.set_discard_threshold(None);
+        pool.set_max_cached_bytes(None);
+        drop(pool.rent(100).unwrap());
+        assert_eq!(pool.stats().cached, 1);
+    }
+
+    #[test]
+    fn aligned_vec_test(){
+        let pool = Arc::new(AlignedPool::<f32, Align64>::with_max_power(10).unwrap());
+        let mut vec: PooledVec<f32, AlignedPool<f32, Align64>> = PooledVec::create(pool.clone());
+        for x in 0..100 {
+            vec.push(x as f32);
+            assert_eq!(vec.as_ptr() as usize % 64, 0);
+        }
+        assert_eq!(vec[99], 99.0);
+        assert!(ArrayPool::<u8>::from_config(PoolConfig { alignment: Some(48), ..PoolConfig::default() }).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn cast_test(){
+        let pool = Arc::new(AlignedPool::<u8, Align16>::with_max_power(8).unwrap());
+        let mut bytes = pool.rent(16).unwrap();
+        bytes[..4].copy_from_slice(&7u32.to_ne_bytes());
+        let words = bytes.cast_mut::<u32>().unwrap();
+        assert_eq!(words.len(), 4);
+        assert_eq!(words[0], 7);
+        words[1] = u32::MAX;
+        assert_eq!(bytes[4..8], [0xff; 4]);
+
+        let mut vec: PooledVec<u8, AlignedPool<u8, Align16>> = PooledVec::create(pool.clone());
+        for x in 0..6 {
+            vec.push(x);
+        }
+        assert_eq!(vec.cast::<u16>().unwrap().len(), 3);
+        vec.push(6);
+        assert!(vec.cast::<u16>().is_err());
+    }
+
+    #[test]
+    fn into_pooled_test(){
+        let pool = Arc::new(CountingPool::new(ArrayPool::<String>::with_max_power(8).unwrap()));
+        let strings: Vec<String> = (0..20).map(|x| x.to_string()).collect();
+        let cloned = strings[..10].to_pooled(&pool);
+        let moved = strings.into_pooled(&pool);
+        assert_eq!(pool.rents(), 2);
+        assert_eq!(&cloned[..], &moved[..10]);
+        assert_eq!((moved.len(), moved.capacity()), (20, 32));
+    }
+
+    #[test]
+    fn collect_pooled_test(){
+        let pool = Arc::new(CountingPool::new(ArrayPool::<u32>::with_max_power(8).unwrap()));
+        let exact = (0..40).map(|x| x * 2).collect_pooled(&pool);
+        assert_eq!(pool.rents(), 1);
+        assert_eq!((exact.len(), exact.capacity()), (40, 64));
+        assert_eq!(exact[39], 78);
+
+        let filtered = (0..40).filter(|x| x % 2 == 0).collect_pooled(&pool);
+        assert_eq!(filtered.len(), 20);
+        assert_eq!(filtered[..3], [0, 2, 4]);
+    }
+
+    #[test]
+    fn interner_test(){
+        let pool = Arc::new(ArrayPool::<u8>::with_max_power(8).unwrap());
+        {
+            let mut interner = PooledInterner::with_chunk_size(pool.clone(), 16);
+            let foo = interner.intern("foo").unwrap();
+            let bar = interner.intern("bar").unwrap();
+            assert_eq!(interner.intern("foo").unwrap(), foo);
+            assert_ne!(foo, bar);
+            let long = interner.intern("a string longer than one chunk").unwrap();
+            assert_eq!(interner.resolve(foo), "foo");
+            assert_eq!(interner.resolve(long), "a string longer than one chunk");
+            assert_eq!(interner.get("bar"), Some(bar));
+            assert_eq!(interner.get("baz"), None);
+            assert_eq!(interner.len(), 3);
+            assert!(interner.intern(&"x".repeat(1000)).is_err());
+        }
+        let stats = pool.stats();
+        assert_eq!((stats.rents, stats.outstanding()), (2, 0));
+    }
+
+    #[test]
+    fn lru_test(){
+        let pool = Arc::new(ArrayPool::<u8>::with_max_power(8).unwrap());
+        let mut cache = PooledLruCache::create(pool.clone(), LruCapacity::Entries(2));
+        cache.put("a", b"first").unwrap();
+        cache.put("b", b"second").unwrap();
+        assert_eq!(cache.get(&"a"), Some(&b"first"[..]));
+        cache.put("c", b"third").unwrap();
+        // "b" was the least recently used
+        assert_eq!(cache.peek(&"b"), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.evict(), Some("a"));
+        assert!(cache.remove(&"c"));
+        assert!(cache.is_empty());
+        assert_eq!(pool.stats().outstanding(), 0);
+
+        let mut cache = PooledLruCache::create(pool.clone(), LruCapacity::Bytes(32));
+        cache.put(1, &[1; 10]).unwrap();
+        cache.put(2, &[2; 10]).unwrap();
+        cache.put(1, &[3; 3]).unwrap();
+        assert_eq!(cache.bytes(), 24);
+        cache.put(3, &[4; 10]).unwrap();
+        assert_eq!(cache.peek(&2), None);
+        assert_eq!(cache.peek(&1), Some(&[3; 3][..]));
+        // Dropping the cache returns the values it still holds
+        drop(cache);
+        assert_eq!(pool.stats().outstanding(), 0);
+    }
+
+    #[test]
+    fn recycler_test(){
+        let pool = Arc::new(ArrayPool::<f32>::with_max_power(8).unwrap());
+        let recycler = Arc::new(BufferRecycler::create(pool.clone(), 32, 2));
+        let (sender, receiver) = std::sync::mpsc::channel::<RecycledBuffer<crate::pool::BorrowingSlice<f32>>>();
+        let consumer = {
+            let recycler = recycler.clone();
+            thread::spawn(move || {
+                let mut sum = 0.0;
+                for buffer in receiver {
+                    sum += buffer[0];
+                    recycler.release(buffer);
+                }
+                sum
+            })
+        };
+        for x in 0..10 {
+            let mut buffer = recycler.acquire().unwrap();
+            buffer[0] = x as f32;
+            sender.send(buffer).unwrap();
+        }
+        drop(sender);
+        assert_eq!(consumer.join().unwrap(), 45.0);
+        assert!(recycler.live() <= 2);
+
+        let first = recycler.try_acquire().unwrap().unwrap();
+        let second = recycler.try_acquire().unwrap().unwrap();
+        assert!(recycler.try_acquire().unwrap().is_none());
+        recycler.retire(first);
+        recycler.release(second);
+        assert_eq!((recycler.live(), recycler.queued()), (1, 1));
+        assert!(pool.stats().allocations <= 2);
+
+        // Dropped buffers are queued back instead of leaking their slot
+        let held = [recycler.acquire().unwrap(), recycler.acquire().unwrap()];
+        drop(held);
+        assert_eq!((recycler.live(), recycler.queued()), (2, 2));
+        let _held = [recycler.acquire().unwrap(), recycler.acquire().unwrap()];
+        assert!(recycler.try_acquire().unwrap().is_none());
+    }
+
+    #[test]
+    fn double_buffer_test(){
+        let pool = Arc::new(ArrayPool::<String>::with_max_power(8).unwrap());
+        {
+            let mut buffers = DoubleBuffer::create(&pool, 10).unwrap();
+            assert_eq!(buffers.capacity(), 16);
+            buffers.back_mut()[0] = "frame 1".to_string();
+            assert_eq!(buffers.front()[0], "");
+            buffers.swap();
+            assert_eq!(buffers.front()[0], "frame 1");
+            assert_eq!(buffers.back()[0], "");
+        }
+        assert_eq!(pool.stats().outstanding(), 0);
+    }
+
+    #[test]
+    fn realtime_test(){
+        let pool = Arc::new(RealtimePool::<f32>::with_classes(&[(100, 2), (10, 1), (16, 1)]));
+        assert_eq!(pool.available(1), 4);
+        assert_eq!(pool.available(17), 2);
+
+        let cloned = pool.clone();
+        thread::spawn(move || {
+            let mut first = cloned.try_rent(12).unwrap();
+            let second = cloned.try_rent(12).unwrap();
+            // Both 16 slots are taken, the next rent falls back to 128
+            assert_eq!((first.len(), second.len()), (16, 16));
+            assert_eq!(cloned.try_rent(12).unwrap().len(), 128);
+            first[0] = 1.5;
+        }).join().unwrap();
+
+        let _large = pool.try_rent(100).unwrap();
+        let _other = pool.try_rent(100).unwrap();
+        assert!(pool.try_rent(100).is_none());
+        assert_eq!(pool.try_rent(16).unwrap()[0], 1.5);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "forbids allocating"))]
+    fn forbid_alloc_test(){
+        let pool = ArrayPool::<u32>::with_max_power(8).unwrap();
+        drop(pool.rent(10).unwrap());
+        pool.forbid_alloc_on_current_thread();
+        let _cached = pool.rent(10).unwrap();
+        assert!(matches!(pool.rent(10), Err(ArrayPoolError::AllocationForbidden)));
+    }
+
+    #[test]
+    fn realtime_backing_test(){
+        let region: &'static mut [u8] = Box::leak(vec![0u8; 1024].into_boxed_slice());
+        let start = region.as_ptr() as usize;
+        let pool = RealtimePool::<u32>::with_backing(region, &[(16, 4), (64, 2)]).unwrap();
+        {
+            let mut large = pool.try_rent(64).unwrap();
+            large[63] = 63;
+            let address = large.as_ptr() as usize;
+            assert!(address >= start && address + 64 * 4 <= start + 1024);
+        }
+        assert_eq!(pool.available(1), 6);
+
+        let small: &'static mut [u8] = Box::leak(vec![0u8; 100].into_boxed_slice());
+        assert!(matches!(RealtimePool::<u32>::with_backing(small, &[(64, 1)]), Err(ArrayPoolError::RegionTooSmall)));
+    }
+
+    #[test]
+    fn array_pool_backing_test(){
+        let region: &'static mut [u8] = Box::leak(vec![0u8; 2048].into_boxed_slice());
+        let (start, end) = (region.as_ptr() as usize, region.as_ptr() as usize + 2048);
+        let pool = ArrayPool::<u32>::with_backing(region, &[(1500 / 4, 1), (16, 2)], PoolConfig::default()).unwrap();
+        assert_eq!(pool.size_classes(), [16, 375]);
+        let arrays = [pool.rent(10).unwrap(), pool.rent(16).unwrap(), pool.rent(17).unwrap()];
+        assert!(arrays.iter().all(|array| array.as_ptr() as usize >= start && array.as_ptr() as usize + array.len() * 4 <= end));
+        assert!(matches!(pool.rent(1), Err(ArrayPoolError::Exhausted)));
+        drop(arrays);
+        // Returned arrays are recycled like any other
+        assert_eq!(pool.rent(16).unwrap().len(), 16);
+        assert_eq!(pool.stats().allocations, 0);
+
+        let small: &'static mut [u8] = Box::leak(vec![0u8; 100].into_boxed_slice());
+        assert!(matches!(ArrayPool::<u32>::with_backing(small, &[(64, 1)], PoolConfig::default()), Err(ArrayPoolError::RegionTooSmall)));
+    }
+
+    #[test]
+    fn fixed_capacity_test(){
+        let config = PoolConfig { max_power: 6, fixed_per_class: Some(2), ..PoolConfig::default() };
+        let pool = Arc::new(ArrayPool::<u32>::from_config(config).unwrap());
+        assert_eq!(pool.stats().cached, 6);
+        let cloned = pool.clone();
+        let (first, second) = thread::spawn(move || {
+            (cloned.rent(10).unwrap(), cloned.rent(10).unwrap())
+        }).join().unwrap();
+        assert!(matches!(pool.rent(10), Err(ArrayPoolError::Exhausted)));
+        drop(first);
+        let _again = pool.rent(10).unwrap();
+        drop(second);
+        assert_eq!(pool.stats().allocations, 6);
+    }
+
+    #[derive(Default)]
+    struct TrackingAllocator {
+        live: AtomicUsize,
+    }
+
+    impl BufferAllocator for TrackingAllocator {
+        unsafe fn allocate(&self, layout: std::alloc::Layout, zeroed: bool) -> *mut u8 {
+            self.live.fetch_add(1, Ordering::SeqCst);
+            if zeroed { std::alloc::alloc_zeroed(layout) } else { std::alloc::alloc(layout) }
+        }
+
+        unsafe fn deallocate(&self, pointer: *mut u8, layout: std::alloc::Layout) {
+            self.live.fetch_sub(1, Ordering::SeqCst);
+            std::alloc::dealloc(pointer, layout)
+        }
+    }
+
+    #[test]
+    fn allocator_test(){
+        let allocator = Arc::new(TrackingAllocator::default());
+        let config = PoolConfig { max_power: 8, ..PoolConfig::default() };
+        {
+            let pool = ArrayPool::<u32>::with_allocator(config, allocator.clone()).unwrap();
+            let _first = pool.rent(10).unwrap();
+            let _second = pool.rent(100).unwrap();
+            assert_eq!(allocator.live.load(Ordering::SeqCst), 2);
+        }
+        assert_eq!(allocator.live.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "mlock")]
+    fn locked_allocator_test(){
+        let config = PoolConfig { max_power: 8, ..PoolConfig::default() };
+        let pool = ArrayPool::<u8>::with_allocator(config, Arc::new(crate::allocator::LockedAllocator)).unwrap();
+        let mut slice = pool.rent(100).unwrap();
+        slice[99] = 99;
+        assert_eq!(slice[99], 99);
+
+    }
+
+    #[test]
+    #[cfg(feature = "mlock")]
+    fn lock_memory_test(){
+        let config = PoolConfig { max_power: 8, lock_memory: true, scrub: true, ..PoolConfig::default() };
+        let pool = ArrayPool::<u8>::from_config(config).unwrap();
+        // Locked arrays take pages of their own, unlike those of the global allocator
+        let page = crate::allocator::page_size();
+        let arrays: Vec<_> = (0..4).map(|_| pool.rent(8).unwrap()).collect();
+        assert!(arrays.iter().all(|array| (array.as_ptr() as usize).is_multiple_of(page)));
+        drop(arrays);
+        assert_eq!(pool.stats().cached, 4);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mlock"))]
+    fn lock_memory_unsupported_test(){
+        let config = PoolConfig { max_power: 8, lock_memory: true, ..PoolConfig::default() };
+        assert!(matches!(ArrayPool::<u8>::from_config(config), Err(ArrayPoolError::LockingUnsupported)));
+    }
+
+    #[test]
+    fn endian_test(){
+        let pool = Arc::new(ArrayPool::<u8>::new());
+        let mut vec = PooledVec::create(pool.clone());
+        vec.put_u16_be(0x0102);
+        vec.put_u32_le(0x03040506);
+        vec.put_varint(300);
+        vec.put_varint_signed(-2);
+        vec.put_f64_be(1.5);
+        assert_eq!(&vec[..6], &[1, 2, 6, 5, 4, 3]);
+        assert_eq!(&vec[6..9], &[0xac, 0x02, 0x03]);
+
+        let mut cursor = ByteCursor::new(vec);
+        assert_eq!(cursor.get_u16_be(), Some(0x0102));
+        assert_eq!(cursor.get_u32_le(), Some(0x03040506));
+        assert_eq!(cursor.get_varint(), Some(300));
+        assert_eq!(cursor.get_varint_signed(), Some(-2));
+        assert_eq!(cursor.get_f64_be(), Some(1.5));
+        assert_eq!(cursor.get_u8(), None);
+
+        let mut cursor = ByteCursor::new(pool.rent(8).unwrap());
+        cursor.put_u64_le(u64::MAX);
+        cursor.set_position(6);
+        assert_eq!(cursor.get_u32_le(), None);
+        assert_eq!(cursor.get_u16_le(), Some(u16::MAX));
+    }
+
+    #[test]
+    fn hexdump_test(){
+        let pool = Arc::new(ArrayPool::<u8>::new());
+        let vec = b"Hello, pool!\x00\x01\xff tail".to_vec().into_pooled(&pool);
+        assert_eq!(vec.hexdump().to_string(), concat!(
+            "00000000  48 65 6c 6c 6f 2c 20 70  6f 6f 6c 21 00 01 ff 20  |Hello, pool!... |\n",
+            "00000010  74 61 69 6c                                       |tail|\n",
+            "00000014",
+        ));
+        let empty = PooledVec::create(pool);
+        assert_eq!(empty.hexdump().to_string(), "00000000");
+    }
+
+    #[test]
+    fn try_push_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_max_power(5).unwrap());
+        let mut vec = PooledVec::create(pool.clone());
+        for x in 0..16 {
+            vec.try_push(x).unwrap();
+        }
+        assert_eq!(vec.capacity(), 16);
+        let rejected = vec.try_push(16).unwrap_err();
+        assert_eq!(rejected.value, 16);
+        assert!(matches!(rejected.error, ArrayPoolError::MaxChunkSizeNotSufficient));
+        assert_eq!(vec.len(), 16);
+        assert_eq!(vec[15], 15);
+
+        let mut vec = PooledVec::create(pool);
+        vec.try_reserve(9).unwrap();
+        assert_eq!(vec.capacity(), 16);
+        assert!(vec.try_reserve(17).is_err());
+        assert_eq!(vec.capacity(), 16);
+    }
+
+    #[test]
+    fn panic_safety_test(){
+        static LIVE: AtomicUsize = AtomicUsize::new(0);
+        static CLONES_LEFT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+        struct Tracked;
+
+        impl Tracked {
+            fn new() -> Self {
+                if CLONES_LEFT.fetch_sub(1, Ordering::SeqCst) == 0 {
+                    panic!("deliberate panic");
+                }
+                LIVE.fetch_add(1, Ordering::SeqCst);
+                Tracked
+            }
+        }
+
+        impl Clone for Tracked {
+            fn clone(&self) -> Self {
+                Tracked::new()
+            }
+        }
+
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                LIVE.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        let pool = Arc::new(ArrayPool::<Tracked>::with_max_power(8).unwrap());
+        CLONES_LEFT.store(5, Ordering::SeqCst);
+        let rented = std::panic::catch_unwind(|| pool.rent_with(16, &mut Tracked::new).map(drop));
+        assert!(rented.is_err());
+        assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+
+        CLONES_LEFT.store(usize::MAX, Ordering::SeqCst);
+        let mut vec = PooledVec::create(pool.clone());
+        for _ in 0..10 {
+            vec.push(Tracked::new());
+        }
+        CLONES_LEFT.store(3, Ordering::SeqCst);
+        let cloned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| vec.clone()));
+        assert!(cloned.is_err());
+        assert_eq!(LIVE.load(Ordering::SeqCst), 10);
+        vec.clear();
+        assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+        assert_eq!(pool.stats().outstanding(), 0);
+    }
+
+    #[test]
+    fn slice_clone_panic_test(){
+        static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+        struct Fragile(usize);
+
+        impl Clone for Fragile {
+            fn clone(&self) -> Self {
+                if self.0 == 5 {
+                    panic!("deliberate panic");
+                }
+                LIVE.fetch_add(1, Ordering::SeqCst);
+                Fragile(self.0)
+            }
+        }
+
+        impl Drop for Fragile {
+            fn drop(&mut self) {
+                LIVE.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        let pool = ArrayPool::<Fragile>::with_max_power(8).unwrap();
+        let mut counter = 0;
+        let slice = pool.rent_with(8, &mut || {
+            LIVE.fetch_add(1, Ordering::SeqCst);
+            counter += 1;
+            Fragile(counter)
+        }).unwrap();
+        let cloned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| slice.clone()));
+        assert!(cloned.is_err());
+        assert_eq!(LIVE.load(Ordering::SeqCst), 8);
+
+        // The partially cloned array went back uninitialized, renting it again is sound
+        let second = pool.rent_with(8, &mut || {
+            LIVE.fetch_add(1, Ordering::SeqCst);
+            Fragile(0)
+        }).unwrap();
+        drop(second);
+        drop(slice);
+        assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+        assert_eq!(pool.stats().outstanding(), 0);
+    }
+
+    #[test]
+    fn get_many_mut_test(){
+        let pool = Arc::new(ArrayPool::<u32>::new());
+        let mut vec = fill_vec(pool, 10);
+        let [a, b] = vec.get_many_mut([2, 7]).unwrap();
+        std::mem::swap(a, b);
+        assert_eq!(vec[2], 7);
+        assert_eq!(vec[7], 2);
+        assert!(vec.get_many_mut([1, 1]).is_none());
+        assert!(vec.get_many_mut([3, 10]).is_none());
+    }
+
+    #[test]
+    fn cursor_mut_test(){
+        let pool = Arc::new(ArrayPool::<u32>::new());
+        let mut vec = fill_vec(pool.clone(), 6);
+        {
+            let mut cursor = vec.cursor_mut(2);
+            assert_eq!(cursor.current().copied(), Some(2));
+            assert_eq!(cursor.remove_current(), Some(2));
+            for x in 10..14 {
+                cursor.insert_before(x);
+            }
+            cursor.insert_after(20);
+            assert_eq!(cursor.index(), 6);
+            cursor.seek(1);
+            assert_eq!(cursor.current().copied(), Some(1));
+            assert!(cursor.move_next());
+            cursor.insert_before(30);
+            cursor.seek(cursor.len());
+            assert!(!cursor.move_next());
+            cursor.insert_before(40);
+        }
+        assert_eq!(&vec[..], &[0, 1, 30, 10, 11, 12, 13, 20, 3, 4, 5, 40]);
+
+        let mut empty = PooledVec::create(pool);
+        empty.cursor_mut(0).insert_after(1);
+        assert_eq!(&empty[..], &[1]);
+    }
+
+    #[test]
+    fn config_roundtrip_test(){
+        let config = PoolConfig { max_power: 10, sharding: Sharding::PerCpu, alignment: Some(64), ..PoolConfig::default() };
+        let pool = ArrayPool::<u8>::from_config(config).unwrap();
+        assert_eq!(pool.config(), config);
+        pool.set_discard_threshold(Some(256));
+        pool.set_scrub(true);
+        drop(pool.rent(100).unwrap());
+
+        let copy = ArrayPool::<u8>::from_config(pool.config()).unwrap();
+        assert_eq!(copy.config(), PoolConfig { discard_above: Some(256), scrub: true, ..config });
+        assert_eq!(copy.max_size(), pool.max_size());
+        assert_eq!(copy.stats().rents, 0);
+    }
+
+    #[test]
+    fn clone_empty_test(){
+        let config = PoolConfig { scrub: true, ..PoolConfig::default() };
+        let pool = ArrayPool::<u8>::with_size_classes(&[1500, 4096, 9000], config).unwrap();
+        pool.set_discard_threshold(Some(9000));
+        // `from_config` falls back to power-of-two classes
+        assert_ne!(ArrayPool::<u8>::from_config(pool.config()).unwrap().size_classes(), pool.size_classes());
+
+        let copy = pool.clone_empty().unwrap();
+        assert_eq!(copy.size_classes(), [1500, 4096, 9000]);
+        assert_eq!(copy.config(), pool.config());
+        assert_eq!(copy.rent(1000).unwrap().len(), 1500);
+    }
+
+    #[test]
+    fn absorb_test(){
+        let pool = ArrayPool::<u64>::with_max_power(8).unwrap();
+        let other = ArrayPool::<u64>::with_max_power(12).unwrap();
+        let rented: Vec<_> = [16, 16, 64, 1024].iter().map(|size| other.rent(*size).unwrap()).collect();
+        drop(rented);
+        assert_eq!(pool.absorb(&other).unwrap(), 3);
+        assert_eq!(pool.stats().cached, 3);
+        assert_eq!(other.stats().cached, 1);
+
+        let _first = pool.rent(16).unwrap();
+        let _second = pool.rent(16).unwrap();
+        assert_eq!(pool.stats().allocations, 0);
+
+        let aligned = ArrayPool::<u64>::from_config(PoolConfig { alignment: Some(64), ..PoolConfig::default() }).unwrap();
+        assert!(matches!(pool.absorb(&aligned), Err(ArrayPoolError::InvalidAlignment)));
+    }
+
+    #[test]
+    fn stats_snapshot_test(){
+        let pool = ArrayPool::<u8>::with_max_power(8).unwrap();
+        drop(pool.rent(16).unwrap());
+        let earlier = pool.stats_snapshot();
+        for _ in 0..3 {
+            drop(pool.rent(16).unwrap());
+        }
+        let delta = pool.stats_snapshot().delta(&earlier);
+        assert_eq!(delta.stats.rents, 3);
+        assert_eq!(delta.stats.allocations, 0);
+        assert_eq!(delta.stats.cached, 1);
+
+        pool.reset_stats();
+        let stats = pool.stats();
+        assert_eq!(stats.rents, 0);
+        assert_eq!(stats.returns, 0);
+        assert_eq!(stats.cached, 1);
+        drop(pool.rent(16).unwrap());
+        assert_eq!(pool.stats_snapshot().delta(&earlier).stats.rents, 1);
+    }
+
+    #[test]
+    fn round_robin_steal_test(){
+        let pool = ArrayPool::<u32>::with_max_power(8).unwrap();
+        // Own a chain before the other threads cache their arrays
+        let _held = pool.rent(10).unwrap();
+        let allocated = std::sync::Barrier::new(2);
+        let cached = std::sync::Barrier::new(3);
+        let stolen = std::sync::Barrier::new(3);
+        let addresses = Mutex::new(vec![]);
+        let (first, second) = thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let slices = [pool.rent(10).unwrap(), pool.rent(10).unwrap()];
+                    addresses.lock().unwrap().push(slices.iter().map(|slice| slice.as_ptr() as usize).collect::<Vec<_>>());
+                    // Allocate every array before any is cached so the workers never steal
+                    allocated.wait();
+                    drop(slices);
+                    cached.wait();
+                    // Stay alive so the cached arrays aren't reclaimed
+                    stolen.wait();
+                });
+            }
+            cached.wait();
+            let stolen_slices = (pool.rent(10).unwrap(), pool.rent(10).unwrap());
+            stolen.wait();
+            stolen_slices
+        });
+
+        assert_eq!(pool.stats().steals, 2);
+        let addresses = addresses.into_inner().unwrap();
+        let owner = |slice: &crate::pool::BorrowingSlice<u32>| addresses.iter().position(|owned| owned.contains(&(slice.as_ptr() as usize)));
+        assert!(owner(&first).is_some());
+        assert_ne!(owner(&first), owner(&second));
+    }
+
+    #[test]
+    fn dead_thread_reclaim_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_max_power(8).unwrap());
+        // Own a chain before the other thread caches its arrays
+        let _held = pool.rent(10).unwrap();
+        let cloned = pool.clone();
+        thread::spawn(move || {
+            let slices = [cloned.rent(10).unwrap(), cloned.rent(10).unwrap()];
+            drop(slices);
+        }).join().unwrap();
+        assert_eq!(pool.stats().cached, 2);
+
+        // The exited thread's arrays moved to the overflow chain, no steal needed
+        let _first = pool.rent(10).unwrap();
+        let _second = pool.rent(10).unwrap();
+        let stats = pool.stats();
+        assert_eq!((stats.allocations, stats.steals), (3, 0));
+    }
+
+    #[test]
+    fn per_thread_cap_test(){
+        let config = PoolConfig { max_power: 8, max_cached_per_thread: Some(1), ..PoolConfig::default() };
+        let pool = ArrayPool::<u32>::from_config(config).unwrap();
+        assert_eq!(pool.config(), config);
+        let _held = pool.rent(10).unwrap();
+        let barrier = std::sync::Barrier::new(2);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                drop([pool.rent(10).unwrap(), pool.rent(10).unwrap(), pool.rent(10).unwrap()]);
+                barrier.wait();
+                barrier.wait();
+            });
+            barrier.wait();
+            // One array stays with the worker, the others overflow to every thread
+            let _first = pool.rent(10).unwrap();
+            let _second = pool.rent(10).unwrap();
+            assert_eq!(pool.stats().steals, 0);
+            let _third = pool.rent(10).unwrap();
+            assert_eq!(pool.stats().steals, 1);
+            barrier.wait();
+        });
+    }
+
+    #[test]
+    fn steal_threshold_test(){
+        let config = PoolConfig { max_power: 8, steal_after_misses: 2, ..PoolConfig::default() };
+        let pool = ArrayPool::<u32>::from_config(config).unwrap();
+        let _held = pool.rent(10).unwrap();
+        let barrier = std::sync::Barrier::new(2);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                drop([pool.rent(10).unwrap(), pool.rent(10).unwrap()]);
+                barrier.wait();
+                barrier.wait();
+            });
+            barrier.wait();
+            let rented: Vec<_> = (0..3).map(|_| pool.rent(10).unwrap()).collect();
+            let stats = pool.stats();
+            // Two misses allocate, the third steals
+            assert_eq!((stats.allocations, stats.steals), (5, 1));
+            drop(rented);
+            barrier.wait();
+        });
+    }
+
+    #[test]
+    fn rent_filled_test(){
+        let bytes = ArrayPool::<u8>::with_max_power(8).unwrap();
+        let slice = bytes.rent_filled(100, 0xab).unwrap();
+        assert_eq!(slice.len(), 128);
+        assert!(slice.iter().all(|x| *x == 0xab));
+
+        let strings = Arc::new(ArrayPool::<String>::with_max_power(8).unwrap());
+        let slice = strings.rent_filled(8, "pool".to_string()).unwrap();
+        assert!(slice.iter().all(|x| x == "pool"));
+        drop(slice);
+
+        let vec = PooledVec::from_elem(strings.clone(), "vec".to_string(), 5);
+        assert_eq!(vec.len(), 5);
+        assert!(vec.iter().all(|x| x == "vec"));
+        assert!(PooledVec::from_elem(strings, String::new(), 0).is_empty());
+    }
+
+    #[test]
+    fn take_replace_test(){
+        let pool = ArrayPool::<u32>::with_max_power(8).unwrap();
+        let mut held = pool.rent_filled(16, 7).unwrap();
+        let taken = held.take();
+        assert!(held.is_empty());
+        assert_eq!(taken.len(), 16);
+        drop(held);
+        assert_eq!(pool.stats().returns, 0);
+
+        let mut held = taken;
+        let old = held.replace(pool.rent_filled(8, 1).unwrap());
+        assert_eq!((old.len(), held.len()), (16, 8));
+        drop(old);
+        assert_eq!(pool.stats().returns, 1);
+    }
+
+    #[test]
+    fn clone_taken_test(){
+        let pool = ArrayPool::<String>::with_max_power(8).unwrap();
+        let mut held = pool.rent_filled(16, "a".to_string()).unwrap();
+        let _taken = held.take();
+        let rents = pool.stats().rents;
+        assert!(held.clone().is_empty());
+        assert!(pool.rent_empty().clone().is_empty());
+        let bytes = ArrayPool::<u8>::with_max_power(8).unwrap();
+        let mut held = bytes.rent(16).unwrap();
+        drop(held.take());
+        assert!(held.clone_copy().is_empty());
+        assert_eq!(pool.stats().rents, rents);
+    }
+
+    #[test]
+    fn static_pool_test(){
+        static BYTES: StaticPool<u8> = StaticPool::with_builder(|| {
+            ArrayPool::from_config(PoolConfig { max_power: 8, ..PoolConfig::default() }).unwrap()
+        });
+        assert_eq!(BYTES.max_size(), 128);
+        drop(BYTES.rent(100).unwrap());
+        let pool = BYTES.get().clone();
+        assert_eq!(pool.stats().rents, 1);
+        assert_eq!(POOL.max_size(), ArrayPool::<u32>::new().max_size());
+    }
+
+    #[test]
+    fn read_buf_test(){
+        let pool = ArrayPool::<u8>::with_max_power(8).unwrap();
+        let mut buf = PooledReadBuf::new(pool.rent(16).unwrap());
+        let mut reader: &[u8] = b"hello, pooled world!";
+        assert_eq!(buf.read_from(&mut reader).unwrap(), 16);
+        assert_eq!(buf.filled(), b"hello, pooled wo");
+        assert_eq!(buf.read_from(&mut reader).unwrap(), 0);
+
+        buf.clear();
+        assert_eq!(buf.read_from(&mut reader).unwrap(), 4);
+        assert_eq!(buf.filled(), b"rld!");
+        assert_eq!(buf.remaining(), 12);
+    }
+
+    #[test]
+    fn frames_test(){
+        let pool = ArrayPool::<u8>::with_max_power(8).unwrap();
+        let mut slice = pool.rent_filled(64, 0).unwrap();
+        for (index, frame) in slice.frames_mut(20).enumerate() {
+            frame.fill(index as u8);
+        }
+        let frames = slice.frames(20);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames.remainder(), &[0; 4]);
+        assert!(frames.enumerate().all(|(index, frame)| frame == [index as u8; 20]));
+    }
+
+    #[cfg(feature = "profiler")]
+    #[test]
+    fn profiler_test(){
+        use crate::profiler::PoolEventKind;
+        let pool = ArrayPool::<u8>::with_max_power(8).unwrap();
+        drop(pool.rent(16).unwrap());
+        assert!(pool.drain_events().is_empty());
+
+        pool.start_profiling(3);
+        drop(pool.rent(16).unwrap());
+        drop(pool.rent(32).unwrap());
+        let kinds: Vec<_> = pool.drain_events().iter().map(|event| (event.kind, event.capacity)).collect();
+        assert_eq!(kinds, [(PoolEventKind::Allocation, 32), (PoolEventKind::Rent, 32), (PoolEventKind::Return, 32)]);
+
+        pool.stop_profiling();
+        drop(pool.rent(16).unwrap());
+        assert!(pool.drain_events().is_empty());
+    }
+
+    #[test]
+    fn passthrough_test(){
+        let pool = PassthroughPool::<u32>::with_max_power(8).unwrap();
+        assert_eq!(pool.rent(20).unwrap().len(), 32);
+        assert!(matches!(pool.rent(129), Err(ArrayPoolError::MaxChunkSizeNotSufficient)));
+        let stats = pool.stats();
+        assert_eq!((stats.rents, stats.returns, stats.allocations, stats.cached), (1, 1, 1, 0));
+
+        for pooled in [true, false] {
+            let pool = Arc::new(if pooled {
+                EitherPool::Left(ArrayPool::<u32>::with_max_power(8).unwrap())
+            } else {
+                EitherPool::Right(PassthroughPool::<u32>::with_max_power(8).unwrap())
+            });
+            let mut vec = PooledVec::create(pool);
+            for i in 0..100 {
+                vec.push(i);
+            }
+            while vec.len() > 10 {
+                vec.pop();
+            }
+            assert_eq!(vec.iter().sum::<u32>(), 45);
+        }
+    }
+
+    #[test]
+    fn packet_pool_test(){
+        let packets = PacketPool::new();
+        let mut packet = packets.rent_packet(1200).unwrap();
+        assert_eq!((packet.len(), packet.capacity()), (1200, 1500));
+        packet.fill(7);
+        assert_eq!(packets.rent_packet(1501).unwrap().capacity(), 4096);
+        assert!(packets.rent_packet(9001).is_err());
+
+        drop(packet);
+        // Cached arrays come back zeroed, not with the previous packet
+        assert!(packets.rent_packet(1200).unwrap().as_full_mut().iter().all(|byte| *byte == 0));
+
+        let mut datagram = packets.rent_max().unwrap();
+        assert_eq!(datagram.len(), 9000);
+        datagram.as_full_mut()[..3].copy_from_slice(b"abc");
+        datagram.set_len(3);
+        assert_eq!(&datagram[..], b"abc");
+        let fixed = PacketPool::with_classes(&[1500], PoolConfig { fixed_per_class: Some(1), ..PoolConfig::default() }).unwrap();
+        let _held = fixed.rent_max().unwrap();
+        assert!(matches!(fixed.rent_max(), Err(ArrayPoolError::Exhausted)));
+
+        let pool = ArrayPool::<u8>::with_size_classes(&[1500, 0, 9000, 4500, 1500], PoolConfig::default()).unwrap();
+        assert_eq!((pool.min_size(), pool.max_size()), (1500, 9000));
+        let expanded = unsafe { pool.expand_buffer(pool.rent(10).unwrap()) }.unwrap();
+        assert_eq!(expanded.len(), 4500);
+        let expanded = unsafe { pool.expand_buffer(expanded) }.unwrap();
+        assert_eq!(unsafe { pool.shrink_buffer(expanded) }.len(), 4500);
+        // No class is at least half of 4500 yet smaller
+        assert_eq!(unsafe { pool.shrink_buffer(pool.rent(4500).unwrap()) }.len(), 4500);
+        assert!(matches!(ArrayPool::<u8>::with_size_classes(&[], PoolConfig::default()), Err(ArrayPoolError::NoSizeClasses)));
+    }
+
+    #[test]
+    fn runtime_size_class_test(){
+        let pool = ArrayPool::<u8>::with_size_classes(&[1500, 9000], PoolConfig::default()).unwrap();
+        assert!(pool.add_size_class(4500));
+        assert!(!pool.add_size_class(4500));
+        assert!(!pool.add_size_class(0));
+        assert_eq!(pool.rent(2000).unwrap().len(), 4500);
+
+        let held = pool.rent(4000).unwrap();
+        drop(pool.rent(4500).unwrap());
+        assert_eq!(pool.stats().cached, 1);
+        assert!(pool.retire_size_class(4500).unwrap());
+        assert!(!pool.retire_size_class(4500).unwrap());
+        assert_eq!(pool.stats().cached, 0);
+        assert_eq!(pool.rent(2000).unwrap().len(), 9000);
+        // Arrays of the retired class are freed instead of cached
+        drop(held);
+        assert_eq!(pool.stats().cached, 1);
+
+        assert_eq!(pool.clone_empty().unwrap().size_classes(), [1500, 9000]);
+        // Only the array of the 9000 class stays cached
+        assert_eq!(pool.stats().cached_bytes, 9000);
+        assert!(pool.retire_size_class(1500).unwrap());
+        assert_eq!(pool.min_size(), 9000);
+        assert!(matches!(pool.retire_size_class(9000), Err(ArrayPoolError::NoSizeClasses)));
+    }
+
+    #[test]
+    fn pool_set_test(){
+        let config = PoolConfig { max_power: 16, ..PoolConfig::default() };
+        let set = PoolSet::from_config(2, config, RoundRobin::default()).unwrap();
+        drop((set.rent(16).unwrap(), set.rent(16).unwrap()));
+        assert!(set.pools().iter().all(|pool| pool.stats().rents == 1));
+        let mut vec = PooledVec::create(Arc::new(set));
+        vec.extend_from_slice(&[1u32; 100]);
+        assert_eq!(vec.len(), 100);
+
+        let set = PoolSet::<u8, _>::from_config(3, config, LeastLoaded).unwrap();
+        let held: Vec<_> = (0..3).map(|_| set.rent(16).unwrap()).collect();
+        assert!(set.pools().iter().all(|pool| pool.stats().rents == 1));
+        drop(held);
+
+        let tenant = Arc::new(AtomicUsize::new(1));
+        let key = tenant.clone();
+        let set = PoolSet::<u8, _>::from_config(2, config, Affinity(move || key.load(Ordering::Relaxed))).unwrap();
+        drop(set.rent(16).unwrap());
+        tenant.store(4, Ordering::Relaxed);
+        drop(set.rent(16).unwrap());
+        assert_eq!(set.pools()[1].stats().rents, 1);
+        assert_eq!(set.stats().rents, 2);
+        assert!(matches!(PoolSet::<u8>::with_pools(vec![], RoundRobin::default()), Err(ArrayPoolError::NoPools)));
+    }
+
+    #[cfg(feature = "codec")]
+    #[test]
+    fn codec_test(){
+        use bytes::BytesMut;
+        use tokio_util::codec::{Decoder, Encoder};
+        use crate::codec::PooledLengthCodec;
+        let pool = Arc::new(ArrayPool::<u8>::with_max_power(12).unwrap());
+        let mut codec = PooledLengthCodec::with_max_frame_length(pool.clone(), 100);
+        let mut wire = BytesMut::new();
+        codec.encode(&b"hello"[..], &mut wire).unwrap();
+        codec.encode(&b"pooled frames"[..], &mut wire).unwrap();
+        assert!(codec.encode(&[0u8; 101][..], &mut wire).is_err());
+
+        let mut partial = wire.split_to(7);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(wire);
+        assert_eq!(&codec.decode(&mut partial).unwrap().unwrap()[..], b"hello");
+        let frame = codec.decode(&mut partial).unwrap().unwrap();
+        assert_eq!(&frame[..], b"pooled frames");
+        assert!(partial.is_empty());
+        drop(frame);
+        assert_eq!(pool.stats().cached, 2);
+
+        partial.extend_from_slice(&1000u32.to_be_bytes());
+        assert!(codec.decode(&mut partial).is_err());
+    }
+
+    #[test]
+    fn recv_into_pooled_test(){
+        use std::net::UdpSocket;
+        use crate::net::{read_into_pooled, recv_from_into_pooled};
+        let pool = ArrayPool::<u8>::with_max_power(12).unwrap();
+        pool.rent(2048).unwrap().fill(0xff);
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.send_to(b"datagram", receiver.local_addr().unwrap()).unwrap();
+        let (packet, from) = recv_from_into_pooled(&receiver, &pool, 1500).unwrap();
+        assert_eq!(&packet[..], b"datagram");
+        assert_eq!(packet.capacity(), 2048);
+        assert_eq!(from, sender.local_addr().unwrap());
+        // The cached array is zeroed before the socket sees it
+        assert!(packet.into_inner()[8..].iter().all(|byte| *byte == 0));
+
+        let mut reader: &[u8] = b"streamed bytes";
+        assert_eq!(&read_into_pooled(&mut reader, &pool, 8).unwrap()[..], b"streamed");
+        assert!(read_into_pooled(&mut reader, &pool, 1 << 12).is_err());
+    }
+
+    #[cfg(feature = "profiler")]
+    #[test]
+    fn rent_sites_test(){
+        use crate::profiler::{write_folded, SiteMetric};
+        fn rent_twice(pool: &ArrayPool<u64>) {
+            drop(pool.rent(16).unwrap());
+            drop(pool.rent(16).unwrap());
+        }
+        let pool = ArrayPool::<u64>::with_max_power(8).unwrap();
+        pool.start_site_profiling();
+        rent_twice(&pool);
+        pool.stop_site_profiling();
+        drop(pool.rent(16).unwrap());
+
+        let sites = pool.take_rent_sites();
+        assert_eq!(sites.len(), 1);
+        assert_eq!((sites[0].rents, sites[0].allocations, sites[0].allocated_bytes), (2, 1, 128));
+        let mut folded = vec![];
+        write_folded(&sites, SiteMetric::AllocatedBytes, &mut folded).unwrap();
+        let folded = String::from_utf8(folded).unwrap();
+        assert!(folded.ends_with("rent_sites_test::rent_twice 128\n"), "{folded}");
+        assert!(pool.take_rent_sites().is_empty());
+    }
+
+    #[test]
+    fn rent_tagged_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_max_power(8).unwrap());
+        let parser = pool.rent_tagged(16, "parser").unwrap();
+        let mut vec = PooledVec::create_tagged(pool.clone(), "index");
+        for i in 0..20 {
+            vec.push(i);
+        }
+        let tags = pool.tag_stats();
+        assert_eq!((tags["parser"].held, tags["parser"].held_bytes), (1, 64));
+        assert_eq!((tags["index"].held, tags["index"].held_bytes), (1, 128));
+        assert_eq!(tags["index"].rents, 3);
+
+        drop(parser);
+        drop(vec.clone());
+        drop(vec);
+        let tags = pool.tag_stats();
+        assert_eq!((tags["parser"].held, tags["index"].held, tags["index"].held_bytes), (0, 0, 0));
+        pool.reset_stats();
+        assert_eq!(pool.tag_stats()["index"].rents, 0);
+    }
+
+    #[test]
+    fn thread_stats_test(){
+        let pool = Arc::new(ArrayPool::<u32>::with_max_power(8).unwrap());
+        drop(pool.rent(16).unwrap());
+        drop(pool.rent(16).unwrap());
+        let worker_pool = pool.clone();
+        // Joining waits for the thread's exit hooks, unlike leaving a scope
+        thread::Builder::new().name("worker".to_string())
+            .spawn(move || drop(worker_pool.rent(32).unwrap()))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let stats = pool.thread_stats();
+        let main = stats.iter().find(|stats| stats.id == thread::current().id()).unwrap();
+        assert_eq!((main.rents, main.hits, main.misses(), main.allocations), (2, 1, 1, 1));
+        assert!(main.alive);
+        let worker = stats.iter().find(|stats| stats.name.as_deref() == Some("worker")).unwrap();
+        assert_eq!((worker.rents, worker.allocations), (1, 1));
+        assert!(!worker.alive);
+    }
+
+    #[test]
+    fn deterministic_test(){
+        use std::sync::Barrier;
+        use crate::pool::set_thread_id;
+        // Workers never steal from each other, only the main thread does
+        let config = PoolConfig { max_power: 8, sharding: Sharding::PerCpu, steal_after_misses: usize::MAX, deterministic: true, ..PoolConfig::default() };
+        let pool = ArrayPool::<u32>::from_config(config).unwrap();
+        let cached = Barrier::new(3);
+        let stolen = Barrier::new(3);
+        let addresses = Mutex::new(vec![]);
+        thread::scope(|scope| {
+            for (id, count) in [(2, 1), (1, 2)] {
+                let (pool, cached, stolen, addresses) = (&pool, &cached, &stolen, &addresses);
+                scope.spawn(move || {
+                    set_thread_id(id);
+                    let arrays: Vec<_> = (0..count).map(|_| pool.rent(16).unwrap()).collect();
+                    addresses.lock().unwrap().extend(arrays.iter().map(|array| (id, array.as_ptr() as usize)));
+                    drop(arrays);
+                    cached.wait();
+                    stolen.wait();
+                });
+            }
+            cached.wait();
+            set_thread_id(0);
+            pool.set_steal_threshold(0);
+            let arrays: Vec<_> = (0..3).map(|_| pool.rent(16).unwrap()).collect();
+            stolen.wait();
+            let owners: Vec<_> = arrays.iter().map(|array| {
+                let address = array.as_ptr() as usize;
+                addresses.lock().unwrap().iter().find(|(_, cached)| *cached == address).unwrap().0
+            }).collect();
+            // Chains are scanned in key order rather than round-robin
+            assert_eq!(owners, [1, 1, 2]);
+        });
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn outstanding_rentals_test() {
+        let pool = ArrayPool::<u64>::new();
+        let small = pool.rent(16).unwrap();
+        let large = pool.rent(1024).unwrap();
+        let rentals = pool.outstanding_rentals();
+        assert_eq!(rentals.iter().map(|rental| rental.capacity).collect::<Vec<_>>(), [1024, 16]);
+        assert_eq!(rentals[0].bytes, 1024 * 8);
+        assert!(rentals.iter().all(|rental| rental.allocated_at.is_some()));
+        drop(large);
+        let reused = pool.rent(1024).unwrap();
+        let rentals = pool.outstanding_rentals();
+        // The array is reused, so its allocation predates this rent
+        assert!(!Arc::ptr_eq(&rentals[0].rented_at, rentals[0].allocated_at.as_ref().unwrap()));
+        // Clones allocating their array record where it happened
+        let copy = small.clone();
+        assert!(pool.outstanding_rentals().iter().all(|rental| rental.allocated_at.is_some()));
+        drop((small, reused, copy));
+        assert!(pool.outstanding_rentals().is_empty());
+    }
+
+    #[test]
+    fn insert_sorted_test() {
+        let pool = Arc::new(ArrayPool::<(u32, &str)>::new());
+        let mut vec = PooledVec::create(pool);
+        for (key, name) in [(5, "e"), (1, "a"), (3, "c"), (3, "c2"), (9, "i"), (0, "z")] {
+            vec.insert_sorted_by_key((key, name), |entry| entry.0);
+        }
+        assert_eq!(&*vec, &[(0, "z"), (1, "a"), (3, "c"), (3, "c2"), (5, "e"), (9, "i")]);
+        assert_eq!(vec.upsert_sorted_by_key((5, "five"), |entry| entry.0), Some((5, "e")));
+        assert_eq!(vec.upsert_sorted_by_key((7, "g"), |entry| entry.0), None);
+        assert_eq!(vec.insert_sorted((3, "b")), 2);
+        assert_eq!(vec.upsert_sorted((9, "i")), Some((9, "i")));
+        assert_eq!(&*vec, &[(0, "z"), (1, "a"), (3, "b"), (3, "c"), (3, "c2"), (5, "five"), (7, "g"), (9, "i")]);
+    }
+
+    #[test]
+    fn flat_map_test() {
+        let key_pool = Arc::new(ArrayPool::<u32>::new());
+        let value_pool = Arc::new(ArrayPool::<String>::new());
+        let entries = (0..10).map(|key| (key * 10, key.to_string()));
+        let mut map = PooledFlatMap::from_sorted(key_pool, value_pool, entries);
+        assert_eq!(map.get(&30).map(String::as_str), Some("3"));
+        assert_eq!(map.get(&35), None);
+        assert_eq!(map.insert(35, "3.5".to_string()), None);
+        assert_eq!(map.insert(30, "three".to_string()), Some("3".to_string()));
+        assert_eq!(map.remove(&0), Some("0".to_string()));
+        let range: Vec<_> = map.range(30..=40).map(|(key, value)| (*key, value.as_str())).collect();
+        assert_eq!(range, [(30, "three"), (35, "3.5"), (40, "4")]);
+        assert_eq!(map.range(41..50).count(), 0);
+        assert_eq!(map.keys(), &[10, 20, 30, 35, 40, 50, 60, 70, 80, 90]);
+        assert_eq!(map.len(), 10);
+
+        // Dropping the map drops its keys and values
+        let live = Arc::new(());
+        let key_pool = Arc::new(ArrayPool::<(u32, Arc<()>)>::new());
+        let mut map = PooledFlatMap::create(key_pool, Arc::new(ArrayPool::<Arc<()>>::new()));
+        for key in 0..3 {
+            map.insert((key, live.clone()), live.clone());
+        }
+        assert_eq!(Arc::strong_count(&live), 7);
+        drop(map);
+        assert_eq!(Arc::strong_count(&live), 1);
+    }
+
+    #[test]
+    fn gap_buffer_test() {
+        let pool = Arc::new(ArrayPool::<u8>::new());
+        let mut text = PooledGapBuffer::create(pool);
+        text.insert_str("hello world");
+        text.move_to(5);
+        text.insert_str(", dear");
+        assert_eq!(text.delete_after(1), 1);
+        text.insert(b' ');
+        assert!(text.move_right());
+        assert_eq!(text.delete_before(100), 13);
+        text.insert_str("bye ");
+        let (front, back) = text.as_slices();
+        assert_eq!((front, back), (&b"bye "[..], &b"orld"[..]));
+        assert_eq!(text.get(5), Some(&b'r'));
+        assert_eq!(text.iter().copied().collect::<Vec<_>>(), b"bye orld");
+        assert_eq!(text.cursor(), 4);
+        text.move_to(0);
+        text.insert_str(&"x".repeat(100));
+        assert_eq!(text.len(), 108);
+        assert!(text.capacity() >= 108);
+        assert_eq!(text.get(107), Some(&b'd'));
+    }
+
+    #[test]
+    fn compact_test() {
+        let pool = ArrayPool::<u8>::new();
+        drop((0..4).map(|_| pool.rent(64).unwrap()).collect::<Vec<_>>());
+        assert_eq!(pool.compact(), Compaction::default());
+        drop(pool.rent(64).unwrap());
+        let held: Vec<_> = (0..2).map(|_| pool.rent(1024).unwrap()).collect();
+        // One rent of 64 since, two misses of 1024
+        assert_eq!(pool.compact(), Compaction { freed: 3, allocated: 2 });
+        let cached: Vec<_> = pool.class_stats().into_iter()
+            .filter(|(_, stats)| stats.cached > 0)
+            .map(|(size, stats)| (size, stats.cached))
+            .collect();
+        assert_eq!(cached, [(64, 1), (1024, 2)]);
+        drop(held);
+        assert_eq!(pool.compact(), Compaction { freed: 5, allocated: 0 });
+        assert_eq!(pool.stats().cached, 0);
+
+        // Prewarmed arrays are not misses, frozen pools keep their arrays
+        let fixed = ArrayPool::<u8>::from_config(PoolConfig { max_power: 8, fixed_per_class: Some(2), ..PoolConfig::default() }).unwrap();
+        drop(fixed.rent(64).unwrap());
+        assert_eq!(fixed.compact(), Compaction::default());
+        assert_eq!(fixed.stats().cached, 10);
+
+        let warm = ArrayPool::<u8>::with_max_power(8).unwrap();
+        warm.prewarm_from_profile(&b"array_pool profile v1\n64 2\n"[..]).unwrap();
+        drop(warm.rent(64).unwrap());
+        assert_eq!(warm.compact(), Compaction { freed: 1, allocated: 0 });
+
+        warm.forbid_alloc_on_current_thread();
+        warm.prewarm_from_profile(&b"array_pool profile v1\n128 2\n"[..]).unwrap();
+        assert_eq!(warm.stats().allocations, 2);
+        warm.allow_alloc_on_current_thread();
+        warm.freeze_allocations();
+        warm.prewarm_from_profile(&b"array_pool profile v1\n128 2\n"[..]).unwrap();
+        assert_eq!(warm.stats().allocations, 2);
+    }
+
+    #[test]
+    fn copy_fast_path_test() {
+        let pool = Arc::new(ArrayPool::<f32>::new());
+        let mut vec = PooledVec::create(pool.clone());
+        vec.extend_from_slice_copy(&[1.0, 2.0, 3.0]);
+        vec.extend_from_slice(&[4.0]);
+        vec.extend_from_slice_copy(&[5.0; 40]);
+        assert_eq!(vec.len(), 44);
+        let copy = vec.clone_copy();
+        assert_eq!(&*copy, &*vec);
+        assert_eq!(copy.capacity(), vec.capacity());
+        let array = pool.rent_filled(16, 7.5).unwrap();
+        assert_eq!(&*array.clone_copy(), &[7.5; 16]);
+    }
+
+    #[test]
+    fn bulk_copy_test() {
+        let pool = ArrayPool::<u32>::new();
+        let mut array = unsafe { pool.rent_or_create_uninitialized(8, false) }.unwrap();
+        array.copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!(array.initialized);
+        array.copy_within(..4, 4);
+        assert_eq!(&*array, &[1, 2, 3, 4, 1, 2, 3, 4]);
+
+        let strings = ArrayPool::<String>::new();
+        let mut filled = strings.rent_filled(8, "a".to_string()).unwrap();
+        let mut empty = unsafe { strings.rent_or_create_uninitialized(8, false) }.unwrap();
+        empty.swap_with_slice(&mut filled);
+        assert!(empty.initialized && !filled.initialized);
+        assert_eq!(empty[7], "a");
+    }
+
+    #[test]
+    fn clone_from_test() {
+        let pool = Arc::new(ArrayPool::<String>::new());
+        let source = pool.rent_filled(16, "a".to_string()).unwrap();
+        let mut target = pool.rent(16).unwrap();
+        let address = target.as_ptr();
+        target.clone_from(&source);
+        assert_eq!(target.as_ptr(), address);
+        assert_eq!(&*target, &*source);
+
+        let mut source = PooledVec::create(pool.clone());
+        let mut target = PooledVec::create(pool.clone());
+        for i in 0..20 {
+            target.push(i.to_string());
+        }
+        for i in 0..5 {
+            source.push((i * 10).to_string());
+        }
+        let address = target.as_ptr();
+        target.clone_from(&source);
+        assert_eq!(target.as_ptr(), address);
+        assert_eq!(&*target, &*source);
+        for i in 5..30 {
+            source.push(i.to_string());
+        }
+        target.clone_from(&source);
+        assert_eq!(&*target, &*source);
+    }
+
+    #[test]
+    fn pooled_lines_test() {
+        let pool = Arc::new(ArrayPool::<u8>::new());
+        let text = "first\r\nsecond line\n\nlast";
+        let lines: Vec<_> = PooledLines::new(text.as_bytes(), pool.clone())
+            .map(|line| String::from_utf8(line.unwrap().to_vec()).unwrap())
+            .collect();
+        assert_eq!(lines, ["first", "second line", "", "last"]);
+        // A tiny buffer splits lines across several reads
+        let reader = std::io::BufReader::with_capacity(3, text.as_bytes());
+        let mut lines = PooledLines::new(reader, pool);
+        assert_eq!(lines.next_line().unwrap(), Some(&b"first"[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"second line"[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b""[..]));
+        assert_eq!(lines.next_line().unwrap(), Some(&b"last"[..]));
+        assert_eq!(lines.next_line().unwrap(), None);
+    }
+
+    #[test]
+    fn std_traits_test() {
+        use std::borrow::Borrow;
+        use std::collections::HashSet;
+        fn total<S: AsRef<[u32]>>(slice: S) -> u32 {
+            slice.as_ref().iter().sum()
+        }
+        fn middle<S: std::ops::Index<std::ops::Range<usize>, Output = [u32]>>(slice: &S) -> &[u32] {
+            &slice[1..3]
+        }
+        let pool = Arc::new(ArrayPool::<u32>::new());
+        let mut array = pool.rent_filled(8, 1).unwrap();
+        array.as_mut()[0] = 5;
+        assert_eq!(total(&array), 12);
+        assert_eq!(middle(&array), &[1, 1]);
+        let mut vec = PooledVec::create(pool);
+        vec.extend_from_slice(&[1, 2, 3, 4]);
+        vec[..2].copy_from_slice(&[9, 8]);
+        assert_eq!(total(&vec), 24);
+        assert_eq!(middle(&vec), &[8, 3]);
+        let set: HashSet<Vec<u32>> = HashSet::from([vec![9, 8, 3, 4]]);
+        assert!(set.contains(Borrow::<[u32]>::borrow(&vec)));
+    }
+
+    #[test]
+    fn failure_injection_test() {
+        let pool = Arc::new(CountingPool::new(ArrayPool::<u32>::new()));
+        pool.fail_nth_rent(3);
+        pool.set_failure(ArrayPoolError::Exhausted);
+        let results: Vec<_> = (0..4).map(|_| unsafe { pool.rent_uninitialized(16) }.err()).collect();
+        assert!(matches!(results[..], [None, None, Some(ArrayPoolError::Exhausted), None]));
+
+        pool.fail_rents_above(Some(64));
+        let mut vec = PooledVec::create(pool.clone());
+        assert!(vec.try_reserve(64).is_ok());
+        assert!(matches!(vec.try_reserve(65), Err(ArrayPoolError::MaxChunkSizeNotSufficient)));
+        pool.fail_rents_above(None);
+        assert!(vec.try_reserve(65).is_ok());
+        assert_eq!(pool.failures(), 2);
+    }
+
+    #[test]
+    fn global_pool_test() {
+        #[derive(Default)]
+        struct Record {
+            values: PooledVec<i16>,
+        }
+        let mut record = Record::default();
+        record.values.push(3);
+        let collected: PooledVec<i16> = (0..100).collect();
+        assert_eq!(collected.len(), 100);
+        assert_eq!(collected[99], 99);
+        let pool = crate::static_pool::global_pool::<i16>();
+        assert_eq!(pool.stats().rents - pool.stats().returns, 2);
+        assert!(Arc::ptr_eq(&pool, &crate::static_pool::global_pool()));
+    }
+
+    #[test]
+    fn snapshot_test() {
+        let pool = Arc::new(ArrayPool::<String>::with_max_power(16).unwrap());
+        let mut vec = PooledVec::create(pool.clone());
+        for i in 0..20 {
+            vec.push(i.to_string());
+        }
+        let snapshot = vec.snapshot();
+        assert!(vec.is_empty());
+        assert_eq!(vec.capacity(), 32);
+        vec.push("next".to_string());
+        let readers: Vec<_> = (0..4).map(|_| snapshot.clone()).collect();
+        let lengths: Vec<_> = thread::scope(|scope| {
+            readers.into_iter()
+                .map(|reader| scope.spawn(move || reader.len()))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        assert_eq!(lengths, [20; 4]);
+        assert_eq!(snapshot[19], "19");
+        assert_eq!(snapshot.ref_count(), 1);
+        drop(snapshot);
+        assert_eq!(pool.stats().rents - pool.stats().returns, 1);
+    }
+
+    #[test]
+    fn append_log_test() {
+        let pool = Arc::new(ArrayPool::<u64>::with_max_power(16).unwrap());
+        let log = crate::append_log::PooledAppendLog::create(pool.clone(), 16);
+        // Keeps every producer alive, so none takes over the open segment of another
+        let done = std::sync::Barrier::new(4);
+        thread::scope(|scope| {
+            for producer in 0..4u64 {
+                let (log, done) = (&log, &done);
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        log.append(producer * 1000 + i);
+                    }
+                    done.wait();
+                });
+            }
+        });
+        // 4 threads fill 6 segments of 16 each, leaving 4 in their open segment
+        assert_eq!(log.completed_segments(), 24);
+        log.seal();
+        let mut values: Vec<u64> = log.take_completed().flat_map(|segment| segment.to_vec()).collect();
+        values.sort_unstable();
+        let expected: Vec<u64> = (0..4).flat_map(|producer| (0..100).map(move |i| producer * 1000 + i)).collect();
+        assert_eq!(values, expected);
+        assert_eq!(log.completed_segments(), 0);
+        assert_eq!(pool.stats().rents, pool.stats().returns);
+    }
+
+    #[test]
+    fn pooled_vec_cursor_test() {
+        use std::io::{Read, Seek, SeekFrom, Write};
+        let pool = Arc::new(ArrayPool::<u8>::new());
+        let mut cursor = crate::cursor::PooledVecCursor::new(PooledVec::create(pool));
+        // Header with a length patched in once the body is written
+        cursor.write_all(b"HDR\0\0\0\0").unwrap();
+        cursor.write_all(&[7; 300]).unwrap();
+        let len = cursor.position() as u32 - 7;
+        cursor.seek(SeekFrom::Start(3)).unwrap();
+        cursor.write_all(&len.to_le_bytes()).unwrap();
+        assert_eq!(cursor.seek(SeekFrom::End(4)).unwrap(), 311);
+        cursor.write_all(b"!").unwrap();
+        assert_eq!(cursor.get_ref().len(), 312);
+        assert_eq!(&cursor.get_ref()[305..], &[7, 7, 0, 0, 0, 0, b'!']);
+        cursor.seek(SeekFrom::Current(-312)).unwrap();
+        let mut header = [0; 7];
+        cursor.read_exact(&mut header).unwrap();
+        assert_eq!(&header, b"HDR\x2c\x01\0\0");
+        assert!(cursor.seek(SeekFrom::Current(-8)).is_err());
+        cursor.set_position(400);
+        assert_eq!(cursor.read(&mut header).unwrap(), 0);
+    }
+
+    #[test]
+    fn slot_map_test() {
+        let value_pool = Arc::new(ArrayPool::<Option<String>>::with_max_power(16).unwrap());
+        let index_pool = Arc::new(ArrayPool::<u32>::with_max_power(16).unwrap());
+        let mut map = PooledSlotMap::create(value_pool.clone(), index_pool);
+        let a = map.insert("a".to_string());
+        let b = map.insert("b".to_string());
+        assert_eq!((map.len(), map.get(a).map(String::as_str)), (2, Some("a")));
+        map.get_mut(b).unwrap().push('!');
+        assert_eq!(map.remove(a).as_deref(), Some("a"));
+        assert!(map.remove(a).is_none());
+        // The slot of `a` is reused under a new generation
+        let c = map.insert("c".to_string());
+        assert_ne!(a, c);
+        assert!(map.get(a).is_none());
+        let entries: Vec<_> = map.iter().map(|(key, value)| (key, value.as_str())).collect();
+        assert_eq!(entries, [(c, "c"), (b, "b!")]);
+        map.clear();
+        assert!(map.is_empty() && !map.contains_key(b) && !map.contains_key(c));
+        let d = map.insert("d".to_string());
+        assert_eq!(map.get(d).map(String::as_str), Some("d"));
+        drop(map);
+        assert_eq!(value_pool.stats().rents, value_pool.stats().returns);
+
+        // Dropping the map drops the values it still holds
+        let live = Arc::new(());
+        let value_pool = Arc::new(ArrayPool::<Option<Arc<()>>>::with_max_power(16).unwrap());
+        let index_pool = Arc::new(ArrayPool::<u32>::with_max_power(16).unwrap());
+        let mut map = PooledSlotMap::create(value_pool, index_pool);
+        let keys: Vec<_> = (0..3).map(|_| map.insert(live.clone())).collect();
+        map.remove(keys[1]);
+        assert_eq!(Arc::strong_count(&live), 3);
+        drop(map);
+        assert_eq!(Arc::strong_count(&live), 1);
+    }
+
+    #[test]
+    fn draining_reader_test() {
+        use std::io::{BufRead, Read};
+        let pool = Arc::new(ArrayPool::<u8>::with_max_power(16).unwrap());
+        let mut body = PooledVec::create(pool.clone());
+        body.extend_from_slice_copy(b"first line\nsecond");
+        let mut reader = crate::cursor::DrainingReader::new(body);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "first line\n");
+        assert_eq!(reader.remaining(), b"second");
+        assert_eq!(pool.stats().returns, 0);
+        let mut rest = vec![];
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"second");
+        assert!(reader.is_exhausted());
+        assert_eq!(pool.stats().rents, pool.stats().returns);
+        assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+        assert!(crate::cursor::DrainingReader::new(PooledVec::create(pool)).is_exhausted());
+    }
+
+    #[cfg(feature = "zerocopy")]
+    #[test]
+    fn zerocopy_records_test() {
+        use zerocopy::byteorder::little_endian::{U16, U32};
+        type Record = [u8; 6];
+        let pool = ArrayPool::<u8>::new();
+        let mut array = pool.rent(16).unwrap();
+        {
+            let records = array.as_records_mut::<Record>().unwrap();
+            assert_eq!(records.len(), 2);
+            records[1] = [1, 0, 0, 0, 0x50, 0];
+        }
+        let records = array.as_records::<[U16; 3]>().unwrap();
+        assert_eq!(records[1].map(|word| word.get()), [1, 0, 0x50]);
+        let entries = array.as_records::<U32>().unwrap();
+        assert_eq!(entries.len(), 4);
+    }
+
+    #[cfg(feature = "profiler")]
+    #[test]
+    fn recent_events_test() {
+        use crate::profiler::PoolEventKind;
+        let pool = ArrayPool::<u8>::with_max_power(8).unwrap();
+        pool.start_profiling(100);
+        drop(pool.rent(16).unwrap());
+        pool.set_max_cached_bytes(Some(0));
+        let events = pool.recent_events();
+        let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(kinds, [PoolEventKind::Allocation, PoolEventKind::Rent, PoolEventKind::Return, PoolEventKind::Eviction]);
+        assert!(events.iter().all(|event| event.thread == thread::current().id()));
+        // Reading the events leaves them in place
+        assert_eq!(pool.recent_events(), events);
+        assert_eq!(pool.drain_events(), events);
+    }
 }
@@ -0,0 +1,97 @@
+use std::io::{self, BufRead};
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+use crate::vec::PooledVec;
+
+/// Append the bytes of `reader` up to and including the next `\n` to
+/// `line`, like `BufRead::read_until`, and return how many were read.
+///
+/// Returns 0 at the end of `reader`. Fails if the pool can't supply a
+/// buffer large enough for the line.
+pub fn read_line_pooled<R: BufRead + ?Sized, P: PoolBackend<u8>>(reader: &mut R, line: &mut PooledVec<u8, P>) -> io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        let (done, used) = match available.iter().position(|byte| *byte == b'\n') {
+            Some(index) => (true, index + 1),
+            None => (available.is_empty(), available.len()),
+        };
+        line.try_reserve(used)?;
+        line.extend_from_slice_copy(&available[..used]);
+        reader.consume(used);
+        read += used;
+        if done { return Ok(read); }
+    }
+}
+
+/// Drop the `\n` or `\r\n` ending `line`.
+fn trim_line_end<P: PoolBackend<u8>>(line: &mut PooledVec<u8, P>) {
+    if line.last() == Some(&b'\n') {
+        line.truncate(line.len() - 1);
+        if line.last() == Some(&b'\r') {
+            line.truncate(line.len() - 1);
+        }
+    }
+}
+
+/// Iterates over the lines of a reader as pooled byte vectors, without
+/// their `\n` or `\r\n`.
+///
+/// Each line rents a buffer as large as the previous one, which the pool
+/// recycles once the line is dropped. `next_line` reads into a single
+/// buffer instead, for lines that don't outlive the next read.
+pub struct PooledLines<R, P: PoolBackend<u8> = ArrayPool<u8>> {
+    reader: R,
+    pool: Arc<P>,
+    buffer: PooledVec<u8, P>,
+    last_len: usize,
+}
+
+impl<R: BufRead, P: PoolBackend<u8>> PooledLines<R, P> {
+    /// Create a new iterator over the lines of `reader`, renting from `pool`.
+    pub fn new(reader: R, pool: Arc<P>) -> Self {
+        Self {
+            reader,
+            buffer: PooledVec::create(pool.clone()),
+            pool,
+            last_len: 0,
+        }
+    }
+
+    /// Read the next line into the buffer of this iterator and return it,
+    /// `None` at the end of the reader.
+    pub fn next_line(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buffer.truncate(0);
+        if read_line_pooled(&mut self.reader, &mut self.buffer)? == 0 { return Ok(None); }
+        trim_line_end(&mut self.buffer);
+        Ok(Some(&self.buffer))
+    }
+
+    /// Gets the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead, P: PoolBackend<u8>> Iterator for PooledLines<R, P> {
+    type Item = io::Result<PooledVec<u8, P>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = PooledVec::with_capacity(self.pool.clone(), self.last_len);
+        match read_line_pooled(&mut self.reader, &mut line) {
+            Ok(0) => None,
+            Ok(read) => {
+                self.last_len = read;
+                trim_line_end(&mut line);
+                Some(Ok(line))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::mem::swap;
+use std::ops::{Deref, DerefMut};
+use std::ptr::drop_in_place;
+use std::rc::Rc;
+
+use crate::backend::PoolBackend;
+use crate::guard::InitGuard;
+use crate::pool::ArrayPoolError;
+use crate::raw_buffer::RawBuffer;
+use crate::stats::PoolStats;
+
+struct LocalChain<T> {
+    chunk_size: usize,
+    chunks: RefCell<Vec<RawBuffer<T>>>,
+    rents: Cell<usize>,
+    returns: Cell<usize>,
+    allocations: Cell<usize>,
+}
+
+/// Represent an array borrowed from a `LocalArrayPool`.
+pub struct LocalBorrowingSlice<T> {
+    array: RawBuffer<T>,
+    chain: Rc<LocalChain<T>>,
+    initialized: bool,
+}
+
+/// A single-threaded variant of `ArrayPool`.
+///
+/// Cached arrays are kept in one chain per size class, without any atomic
+/// or lock, so the pool can't be shared between threads.
+pub struct LocalArrayPool<T> {
+    chunk_map: BTreeMap<usize, Rc<LocalChain<T>>>
+}
+
+impl<T> LocalChain<T> {
+    fn new(size_power: u8) -> Rc<Self> {
+        Rc::new(Self {
+            chunk_size: 1usize << size_power,
+            chunks: RefCell::new(vec![]),
+            rents: Cell::new(0),
+            returns: Cell::new(0),
+            allocations: Cell::new(0),
+        })
+    }
+
+    fn rent_with<F: FnMut() -> T>(self: &Rc<Self>, fabricator: &mut F) -> LocalBorrowingSlice<T> {
+        let mut slice = unsafe { self.rent_or_create_uninitialized(false) };
+        // Cached arrays had their elements dropped on return, avoid dropping them again
+        let mut guard = unsafe { InitGuard::new(&mut slice) };
+        for _ in 0..self.chunk_size {
+            guard.push(fabricator());
+        }
+        guard.finish();
+        slice.initialized = true;
+        slice
+    }
+
+    unsafe fn rent_or_create_uninitialized(self: &Rc<Self>, zeroed: bool) -> LocalBorrowingSlice<T> {
+        self.rents.set(self.rents.get() + 1);
+        let array = match self.chunks.borrow_mut().pop() {
+            Some(cached) => cached,
+            None => {
+                self.allocations.set(self.allocations.get() + 1);
+                RawBuffer::new(self.chunk_size, zeroed)
+            }
+        };
+        LocalBorrowingSlice {
+            array,
+            chain: self.clone(),
+            initialized: false,
+        }
+    }
+
+    fn stats(&self) -> PoolStats {
+        let cached = self.chunks.borrow().len();
+        PoolStats {
+            rents: self.rents.get(),
+            returns: self.returns.get(),
+            allocations: self.allocations.get(),
+            steals: 0,
+            discards: 0,
+            cached,
+            cached_bytes: cached * self.chunk_size * size_of::<T>(),
+            ..PoolStats::default()
+        }
+    }
+}
+
+impl<T> Drop for LocalBorrowingSlice<T> {
+    fn drop(&mut self) {
+        if self.array.is_empty() { return; }
+        if self.initialized {
+            unsafe {
+                for elem in self.array.get_ref_mut().iter_mut() {
+                    drop_in_place(elem);
+                }
+            }
+        }
+        let mut store = RawBuffer::<T>::empty();
+        swap(&mut store, &mut self.array);
+        self.chain.chunks.borrow_mut().push(store);
+        self.chain.returns.set(self.chain.returns.get() + 1);
+    }
+}
+
+impl<T> Deref for LocalBorrowingSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.array.deref()
+    }
+}
+
+impl<T> DerefMut for LocalBorrowingSlice<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.array.deref_mut()
+    }
+}
+
+impl<T> LocalArrayPool<T> {
+    /// Create a new `LocalArrayPool` with `max_power`.
+    ///
+    /// See `ArrayPool::with_max_power`.
+    pub fn with_max_power(max_power: u8) -> Result<Self, ArrayPoolError> {
+        if max_power < 4 { return Err(ArrayPoolError::MaxPowerTooSmall); }
+        let mut map = BTreeMap::new();
+        for x in 3..max_power {
+            map.insert(1usize << x, LocalChain::new(x));
+        }
+        Ok(Self {
+            chunk_map: map
+        })
+    }
+
+    /// Create a new `LocalArrayPool` with `max_power` of `target_pointer_width - 1`.
+    pub fn new() -> Self {
+        Self::with_max_power((usize::BITS - 1) as u8).unwrap()
+    }
+
+    fn get_chain(&self, minimum_capacity: usize) -> Result<&Rc<LocalChain<T>>, ArrayPoolError> {
+        self.chunk_map.range(minimum_capacity..)
+            .next()
+            .map(|(_, chain)| chain)
+            .ok_or(ArrayPoolError::MaxChunkSizeNotSufficient)
+    }
+
+    /// Rent a new array with `minimum_capacity`, each element is
+    /// initialized by `fabricator`.
+    pub fn rent_with<F: FnMut() -> T>(&self, minimum_capacity: usize, fabricator: &mut F) -> Result<LocalBorrowingSlice<T>, ArrayPoolError> {
+        Ok(self.get_chain(minimum_capacity)?.rent_with(fabricator))
+    }
+
+    /// Rent a new array with `minimum_capacity`.
+    ///
+    /// If no cached array was found, create a new one without initializing it,
+    /// zero if needed.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned array may be uninitialized and will not be
+    /// dropped when the array is returned.
+    pub unsafe fn rent_or_create_uninitialized(&self, minimum_capacity: usize, zeroed: bool) -> Result<LocalBorrowingSlice<T>, ArrayPoolError> {
+        Ok(self.get_chain(minimum_capacity)?.rent_or_create_uninitialized(zeroed))
+    }
+
+    /// Double the capacity of `old_buffer`. New slots won't be initialized.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is treated as uninitialized, its elements
+    /// will not be dropped when it is returned.
+    pub unsafe fn expand_buffer(&self, mut old_buffer: LocalBorrowingSlice<T>) -> Result<LocalBorrowingSlice<T>, ArrayPoolError> {
+        let old_size = old_buffer.len();
+        let mut new_buffer = self.rent_or_create_uninitialized(old_size * 2, false)?;
+        for i in 0..old_size {
+            swap(&mut old_buffer[i], &mut new_buffer[i]);
+        }
+        old_buffer.initialized = false;
+        Ok(new_buffer)
+    }
+
+    /// Halve the capacity of `old_buffer`. Old slots won't be dropped.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is treated as uninitialized, its elements
+    /// will not be dropped when it is returned.
+    pub unsafe fn shrink_buffer(&self, mut old_buffer: LocalBorrowingSlice<T>) -> LocalBorrowingSlice<T> {
+        let new_size = old_buffer.len() / 2;
+        match self.rent_or_create_uninitialized(new_size, false) {
+            Ok(mut new_buffer) => {
+                for i in 0..new_size {
+                    swap(&mut old_buffer[i], &mut new_buffer[i]);
+                }
+                old_buffer.initialized = false;
+                new_buffer
+            }
+            Err(_) => old_buffer
+        }
+    }
+
+    /// Gets the smallest supported capacity.
+    pub fn min_size(&self) -> usize {
+        *self.chunk_map.first_key_value().unwrap().0
+    }
+
+    /// Gets the largest supported capacity.
+    pub fn max_size(&self) -> usize {
+        *self.chunk_map.last_key_value().unwrap().0
+    }
+
+    /// Gets the usage counters of this pool, summed over every size class.
+    pub fn stats(&self) -> PoolStats {
+        self.chunk_map.values()
+            .fold(PoolStats::default(), |acc, chain| acc + chain.stats())
+    }
+}
+
+impl<T: Default> LocalArrayPool<T> {
+    /// Rent a new array with `minimum_capacity`, each element is
+    /// initialized by the default constructor.
+    pub fn rent(&self, minimum_capacity: usize) -> Result<LocalBorrowingSlice<T>, ArrayPoolError> {
+        self.rent_with(minimum_capacity, &mut T::default)
+    }
+}
+
+impl<T> Default for LocalArrayPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PoolBackend<T> for LocalArrayPool<T> {
+    type Buffer = LocalBorrowingSlice<T>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.rent_or_create_uninitialized(minimum_capacity, false)
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.rent_or_create_uninitialized(0, false)
+    }
+
+    fn return_buffer(&self, mut buffer: Self::Buffer) {
+        buffer.initialized = false;
+        drop(buffer);
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        LocalArrayPool::expand_buffer(self, buffer)
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        LocalArrayPool::shrink_buffer(self, buffer)
+    }
+
+    fn min_size(&self) -> usize {
+        LocalArrayPool::min_size(self)
+    }
+
+    fn max_size(&self) -> usize {
+        LocalArrayPool::max_size(self)
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::{ArrayPool, ArrayPoolError};
+use crate::vec::PooledVec;
+
+const NONE: usize = usize::MAX;
+
+/// Bounds the content of a `PooledLruCache`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LruCapacity {
+    /// Hold at most this many entries.
+    Entries(usize),
+    /// Hold buffers totalling at most this many bytes, counted by the
+    /// capacity of the rented buffers.
+    Bytes(usize),
+}
+
+struct Entry<K, B> {
+    key: K,
+    value: B,
+    len: usize,
+    prev: usize,
+    next: usize,
+}
+
+/// A least-recently-used cache of byte buffers rented from a pool.
+///
+/// The entries and free slots are kept in vectors renting from the
+/// process-wide pools, see `global_pool`. Evicted values return to the
+/// pool right away.
+pub struct PooledLruCache<K: Hash + Eq + Clone + Send + 'static, P: PoolBackend<u8> = ArrayPool<u8>> where P::Buffer: Send + 'static {
+    pool: Arc<P>,
+    capacity: LruCapacity,
+    map: HashMap<K, usize>,
+    entries: PooledVec<Option<Entry<K, P::Buffer>>>,
+    free: PooledVec<usize>,
+    head: usize,
+    tail: usize,
+    bytes: usize,
+}
+
+impl<K: Hash + Eq + Clone + Send + 'static, P: PoolBackend<u8>> PooledLruCache<K, P> where P::Buffer: Send + 'static {
+    /// Create a new cache bounded by `capacity`.
+    pub fn create(pool: Arc<P>, capacity: LruCapacity) -> Self {
+        Self {
+            pool,
+            capacity,
+            map: HashMap::new(),
+            entries: PooledVec::new(),
+            free: PooledVec::new(),
+            head: NONE,
+            tail: NONE,
+            bytes: 0,
+        }
+    }
+
+    fn entry(&self, index: usize) -> &Entry<K, P::Buffer> {
+        self.entries[index].as_ref().unwrap()
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut Entry<K, P::Buffer> {
+        self.entries[index].as_mut().unwrap()
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = {
+            let entry = self.entry(index);
+            (entry.prev, entry.next)
+        };
+        if prev == NONE { self.head = next; } else { self.entry_mut(prev).next = next; }
+        if next == NONE { self.tail = prev; } else { self.entry_mut(next).prev = prev; }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        let head = self.head;
+        {
+            let entry = self.entry_mut(index);
+            entry.prev = NONE;
+            entry.next = head;
+        }
+        if head == NONE { self.tail = index; } else { self.entry_mut(head).prev = index; }
+        self.head = index;
+    }
+
+    fn remove_at(&mut self, index: usize) -> Entry<K, P::Buffer> {
+        self.unlink(index);
+        let entry = self.entries[index].take().unwrap();
+        self.free.push(index);
+        self.map.remove(&entry.key);
+        self.bytes -= entry.value.len();
+        entry
+    }
+
+    fn over_capacity(&self) -> bool {
+        match self.capacity {
+            LruCapacity::Entries(entries) => self.map.len() > entries,
+            LruCapacity::Bytes(bytes) => self.bytes > bytes,
+        }
+    }
+
+    /// Gets the value of `key`, marking it as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&[u8]> {
+        let index = *self.map.get(key)?;
+        self.unlink(index);
+        self.push_front(index);
+        let entry = self.entry(index);
+        Some(&entry.value[..entry.len])
+    }
+
+    /// Gets the value of `key` without changing its recency.
+    pub fn peek(&self, key: &K) -> Option<&[u8]> {
+        let entry = self.entry(*self.map.get(key)?);
+        Some(&entry.value[..entry.len])
+    }
+
+    /// Copy `value` into a pooled buffer cached under `key`, then evict the
+    /// least recently used entries until the cache fits its capacity.
+    pub fn put(&mut self, key: K, value: &[u8]) -> Result<(), ArrayPoolError> {
+        let mut buffer = unsafe { self.pool.rent_uninitialized(value.len()) }?;
+        buffer[..value.len()].copy_from_slice(value);
+        if let Some(index) = self.map.get(&key).copied() {
+            self.remove_at(index);
+        }
+        self.bytes += buffer.len();
+        let entry = Entry { key: key.clone(), value: buffer, len: value.len(), prev: NONE, next: NONE };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.entries[index] = Some(entry);
+                index
+            }
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+        self.map.insert(key, index);
+        self.push_front(index);
+        while self.over_capacity() {
+            self.evict();
+        }
+        Ok(())
+    }
+
+    /// Remove the value of `key`, returning its buffer to the pool.
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.map.get(key).copied() {
+            Some(index) => {
+                self.remove_at(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the least recently used entry and return its key.
+    pub fn evict(&mut self) -> Option<K> {
+        if self.tail == NONE { return None; }
+        Some(self.remove_at(self.tail).key)
+    }
+
+    /// Gets the number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check whether this cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Gets the total capacity in bytes of the cached buffers.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl<K: Hash + Eq + Clone + Send + 'static, P: PoolBackend<u8>> Drop for PooledLruCache<K, P> where P::Buffer: Send + 'static {
+    fn drop(&mut self) {
+        // Pooled vectors don't drop their elements, return the values here
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,93 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::pool::{ArrayPool, ArrayPoolError};
+use crate::stats::PoolStats;
+
+trait ManagedPool: Send + Sync {
+    fn stats(&self) -> PoolStats;
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+impl<T: Send + 'static> ManagedPool for ArrayPool<T> {
+    fn stats(&self) -> PoolStats {
+        ArrayPool::stats(self)
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// Owns one `ArrayPool` per element type, creating each of them on demand.
+pub struct PoolManager {
+    max_power: u8,
+    pools: RwLock<HashMap<TypeId, Arc<dyn ManagedPool>>>
+}
+
+impl PoolManager {
+    /// Create a new `PoolManager` whose pools are created with `max_power`.
+    ///
+    /// See `ArrayPool::with_max_power`.
+    pub fn with_max_power(max_power: u8) -> Result<Self, ArrayPoolError> {
+        // Validate the configuration once instead of on every pool creation
+        ArrayPool::<()>::with_max_power(max_power)?;
+        Ok(Self {
+            max_power,
+            pools: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Create a new `PoolManager` whose pools are created with `ArrayPool::new`.
+    pub fn new() -> Self {
+        Self::with_max_power((usize::BITS - 1) as u8).unwrap()
+    }
+
+    /// Gets the pool of element type `T`, creating it if needed.
+    pub fn pool<T: Send + 'static>(&self) -> Arc<ArrayPool<T>> {
+        let id = TypeId::of::<T>();
+        if let Some(pool) = self.pools.read().unwrap().get(&id) {
+            return Self::downcast(pool.clone());
+        }
+        let mut lock_guard = self.pools.write().unwrap();
+        let pool = lock_guard.entry(id)
+            .or_insert_with(|| Arc::new(ArrayPool::<T>::with_max_power(self.max_power).unwrap()))
+            .clone();
+        Self::downcast(pool)
+    }
+
+    /// Make `pool` the pool of element type `T`, replacing the current one.
+    ///
+    /// Holders of the replaced pool keep using it.
+    pub fn set_pool<T: Send + 'static>(&self, pool: Arc<ArrayPool<T>>) {
+        self.pools.write().unwrap().insert(TypeId::of::<T>(), pool);
+    }
+
+    fn downcast<T: Send + 'static>(pool: Arc<dyn ManagedPool>) -> Arc<ArrayPool<T>> {
+        pool.into_any().downcast::<ArrayPool<T>>().unwrap()
+    }
+
+    /// Gets the number of pools created so far.
+    pub fn len(&self) -> usize {
+        self.pools.read().unwrap().len()
+    }
+
+    /// Check whether no pool has been created yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the usage counters of every managed pool, summed together.
+    pub fn stats(&self) -> PoolStats {
+        self.pools.read().unwrap().values()
+            .fold(PoolStats::default(), |acc, pool| acc + pool.stats())
+    }
+}
+
+impl Default for PoolManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,74 @@
+use std::io::{self, Read};
+use std::net::{SocketAddr, UdpSocket};
+
+use crate::packet::Packet;
+use crate::pool::ArrayPool;
+
+/// Rent a buffer of at least `max_len` bytes to receive into.
+///
+/// Arrays are zeroed so every byte handed to the socket is initialized,
+/// cached ones may hold bytes that never were.
+fn rent_receive_buffer(pool: &ArrayPool<u8>, max_len: usize) -> io::Result<Packet> {
+    let buffer = pool.rent(max_len)?;
+    Ok(Packet::from_buffer(buffer, max_len))
+}
+
+/// Receive a datagram of up to `max_len` bytes from the connected `socket`
+/// straight into a buffer rented from `pool`.
+///
+/// The packet is as long as the datagram, the rest of a longer datagram is
+/// discarded like with `UdpSocket::recv`.
+pub fn recv_into_pooled(socket: &UdpSocket, pool: &ArrayPool<u8>, max_len: usize) -> io::Result<Packet> {
+    let mut packet = rent_receive_buffer(pool, max_len)?;
+    let received = socket.recv(&mut packet)?;
+    packet.set_len(received);
+    Ok(packet)
+}
+
+/// Receive a datagram of up to `max_len` bytes from `socket` straight into
+/// a buffer rented from `pool`, along with its sender.
+pub fn recv_from_into_pooled(socket: &UdpSocket, pool: &ArrayPool<u8>, max_len: usize) -> io::Result<(Packet, SocketAddr)> {
+    let mut packet = rent_receive_buffer(pool, max_len)?;
+    let (received, sender) = socket.recv_from(&mut packet)?;
+    packet.set_len(received);
+    Ok((packet, sender))
+}
+
+/// Read once from `reader`, e.g. a `TcpStream`, straight into a buffer of
+/// up to `max_len` bytes rented from `pool`.
+///
+/// An empty packet means the end of the stream was reached.
+pub fn read_into_pooled<R: Read + ?Sized>(reader: &mut R, pool: &ArrayPool<u8>, max_len: usize) -> io::Result<Packet> {
+    let mut packet = rent_receive_buffer(pool, max_len)?;
+    let read = reader.read(&mut packet)?;
+    packet.set_len(read);
+    Ok(packet)
+}
+
+/// Async variant of `recv_into_pooled`.
+#[cfg(feature = "tokio")]
+pub async fn recv_into_pooled_async(socket: &tokio::net::UdpSocket, pool: &ArrayPool<u8>, max_len: usize) -> io::Result<Packet> {
+    let mut packet = rent_receive_buffer(pool, max_len)?;
+    let received = socket.recv(&mut packet).await?;
+    packet.set_len(received);
+    Ok(packet)
+}
+
+/// Async variant of `recv_from_into_pooled`.
+#[cfg(feature = "tokio")]
+pub async fn recv_from_into_pooled_async(socket: &tokio::net::UdpSocket, pool: &ArrayPool<u8>, max_len: usize) -> io::Result<(Packet, SocketAddr)> {
+    let mut packet = rent_receive_buffer(pool, max_len)?;
+    let (received, sender) = socket.recv_from(&mut packet).await?;
+    packet.set_len(received);
+    Ok((packet, sender))
+}
+
+/// Async variant of `read_into_pooled`.
+#[cfg(feature = "tokio")]
+pub async fn read_into_pooled_async<R: tokio::io::AsyncRead + Unpin + ?Sized>(reader: &mut R, pool: &ArrayPool<u8>, max_len: usize) -> io::Result<Packet> {
+    use tokio::io::AsyncReadExt;
+    let mut packet = rent_receive_buffer(pool, max_len)?;
+    let read = reader.read(&mut packet).await?;
+    packet.set_len(read);
+    Ok(packet)
+}
@@ -0,0 +1,122 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use crate::config::PoolConfig;
+use crate::pool::{ArrayPool, ArrayPoolError, BorrowingSlice};
+
+/// Size classes of `PacketPool::new`: an Ethernet MTU, a page and a jumbo frame.
+pub const DEFAULT_PACKET_CLASSES: [usize; 3] = [1500, 4096, 9000];
+
+/// A byte pool sized for network packets, see `ArrayPool::with_size_classes`.
+pub struct PacketPool {
+    pool: Arc<ArrayPool<u8>>,
+}
+
+/// A packet rented from a `PacketPool` or received by the helpers of
+/// `net`, holding its length apart from the capacity of its array.
+///
+/// Dereferences to the first `len` bytes.
+pub struct Packet {
+    buffer: BorrowingSlice<u8>,
+    len: usize,
+}
+
+impl PacketPool {
+    /// Create a new `PacketPool` with `DEFAULT_PACKET_CLASSES`.
+    pub fn new() -> Self {
+        Self::with_classes(&DEFAULT_PACKET_CLASSES, PoolConfig::default()).unwrap()
+    }
+
+    /// Create a new `PacketPool` whose arrays have one of the lengths of `classes`.
+    pub fn with_classes(classes: &[usize], config: PoolConfig) -> Result<Self, ArrayPoolError> {
+        Ok(Self {
+            pool: Arc::new(ArrayPool::with_size_classes(classes, config)?),
+        })
+    }
+
+    /// Gets the underlying pool.
+    pub fn pool(&self) -> &Arc<ArrayPool<u8>> {
+        &self.pool
+    }
+
+    /// Rent a packet of `len` bytes from the smallest class that fits.
+    ///
+    /// The whole array is zeroed: cached arrays may hold bytes that were
+    /// never initialized, which `as_full_mut` would expose.
+    pub fn rent_packet(&self, len: usize) -> Result<Packet, ArrayPoolError> {
+        let buffer = self.pool.rent(len)?;
+        Ok(Packet { buffer, len })
+    }
+
+    /// Rent a packet of the largest class, as long as its capacity, to
+    /// receive a datagram of unknown size.
+    pub fn rent_max(&self) -> Result<Packet, ArrayPoolError> {
+        let mut packet = self.rent_packet(self.pool.max_size())?;
+        packet.len = packet.capacity();
+        Ok(packet)
+    }
+}
+
+impl Default for PacketPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Packet {
+    /// Wrap `buffer`, whose first `len` bytes are the packet.
+    ///
+    /// Panics if `len` exceeds the length of `buffer`.
+    pub fn from_buffer(buffer: BorrowingSlice<u8>, len: usize) -> Self {
+        let mut packet = Self { buffer, len: 0 };
+        packet.set_len(len);
+        packet
+    }
+
+    /// Gets the length of this packet.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether this packet is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the capacity of the underlying array.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Set the length of this packet, e.g. after receiving into `as_full_mut`.
+    ///
+    /// Panics if `len` exceeds the capacity.
+    pub fn set_len(&mut self, len: usize) {
+        assert!(len <= self.capacity(), "packet length {len} exceeds capacity {}", self.capacity());
+        self.len = len;
+    }
+
+    /// Gets the whole underlying array, regardless of the length.
+    pub fn as_full_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    /// Gets the underlying array.
+    pub fn into_inner(self) -> BorrowingSlice<u8> {
+        self.buffer
+    }
+}
+
+impl Deref for Packet {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer[..self.len]
+    }
+}
+
+impl DerefMut for Packet {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer[..self.len]
+    }
+}
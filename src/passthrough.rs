@@ -0,0 +1,318 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::drop_in_place;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backend::PoolBackend;
+use crate::guard::InitGuard;
+use crate::pool::ArrayPoolError;
+use crate::raw_buffer::RawBuffer;
+use crate::stats::PoolStats;
+
+#[derive(Default)]
+struct Counters {
+    rents: AtomicUsize,
+    returns: AtomicUsize,
+}
+
+/// A pool that caches nothing: every rent allocates a new array and every
+/// return frees it.
+///
+/// Arrays have the same capacities as with an `ArrayPool` of the same
+/// `max_power`, so both can be swapped to measure what pooling gains.
+pub struct PassthroughPool<T> {
+    min_size: usize,
+    max_size: usize,
+    counters: Arc<Counters>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+/// Represent an array allocated by a `PassthroughPool`, freed on drop.
+pub struct PassthroughBuffer<T> {
+    array: RawBuffer<T>,
+    counters: Arc<Counters>,
+    initialized: bool,
+}
+
+/// A pool backend chosen at runtime between two others, e.g. an `ArrayPool`
+/// and a `PassthroughPool` picked from the configuration.
+pub enum EitherPool<A, B> {
+    Left(A),
+    Right(B),
+}
+
+/// A buffer rented from an `EitherPool`.
+pub enum EitherBuffer<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<T> PassthroughPool<T> {
+    /// Create a new `PassthroughPool` with `max_power`.
+    ///
+    /// See `ArrayPool::with_max_power`.
+    pub fn with_max_power(max_power: u8) -> Result<Self, ArrayPoolError> {
+        if max_power < 4 { return Err(ArrayPoolError::MaxPowerTooSmall); }
+        Ok(Self {
+            min_size: 1 << 3,
+            max_size: 1 << (max_power - 1),
+            counters: Arc::new(Counters::default()),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Create a new `PassthroughPool` with `max_power` of `target_pointer_width - 1`.
+    pub fn new() -> Self {
+        Self::with_max_power((usize::BITS - 1) as u8).unwrap()
+    }
+
+    fn capacity_for(&self, minimum_capacity: usize) -> Result<usize, ArrayPoolError> {
+        let capacity = minimum_capacity.max(self.min_size).checked_next_power_of_two();
+        capacity.filter(|capacity| *capacity <= self.max_size)
+            .ok_or(ArrayPoolError::MaxChunkSizeNotSufficient)
+    }
+
+    /// Allocate a new array with `minimum_capacity`, each element is
+    /// initialized by `fabricator`.
+    pub fn rent_with<F: FnMut() -> T>(&self, minimum_capacity: usize, fabricator: &mut F) -> Result<PassthroughBuffer<T>, ArrayPoolError> {
+        let mut buffer = unsafe { self.rent_or_create_uninitialized(minimum_capacity, false) }?;
+        let len = buffer.len();
+        let mut guard = unsafe { InitGuard::new(&mut buffer) };
+        for _ in 0..len {
+            guard.push(fabricator());
+        }
+        guard.finish();
+        buffer.initialized = true;
+        Ok(buffer)
+    }
+
+    /// Allocate a new array with `minimum_capacity` without initializing it,
+    /// zero if needed.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned array is uninitialized and will not be
+    /// dropped when the array is freed.
+    pub unsafe fn rent_or_create_uninitialized(&self, minimum_capacity: usize, zeroed: bool) -> Result<PassthroughBuffer<T>, ArrayPoolError> {
+        let array = RawBuffer::new(self.capacity_for(minimum_capacity)?, zeroed);
+        self.counters.rents.fetch_add(1, Ordering::Relaxed);
+        Ok(PassthroughBuffer {
+            array,
+            counters: self.counters.clone(),
+            initialized: false,
+        })
+    }
+
+    /// Double the capacity of `old_buffer`. New slots won't be initialized.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is treated as uninitialized, its elements
+    /// will not be dropped when it is freed.
+    pub unsafe fn expand_buffer(&self, mut old_buffer: PassthroughBuffer<T>) -> Result<PassthroughBuffer<T>, ArrayPoolError> {
+        let old_size = old_buffer.len();
+        let mut new_buffer = self.rent_or_create_uninitialized(old_size * 2, false)?;
+        std::ptr::copy_nonoverlapping(old_buffer.as_ptr(), new_buffer.as_mut_ptr(), old_size);
+        old_buffer.initialized = false;
+        Ok(new_buffer)
+    }
+
+    /// Halve the capacity of `old_buffer`. Old slots won't be dropped.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is treated as uninitialized, its elements
+    /// will not be dropped when it is freed.
+    pub unsafe fn shrink_buffer(&self, mut old_buffer: PassthroughBuffer<T>) -> PassthroughBuffer<T> {
+        let new_size = old_buffer.len() / 2;
+        if new_size < self.min_size { return old_buffer; }
+        match self.rent_or_create_uninitialized(new_size, false) {
+            Ok(mut new_buffer) => {
+                std::ptr::copy_nonoverlapping(old_buffer.as_ptr(), new_buffer.as_mut_ptr(), new_size);
+                old_buffer.initialized = false;
+                new_buffer
+            }
+            Err(_) => old_buffer
+        }
+    }
+
+    /// Gets the smallest supported capacity.
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Gets the largest supported capacity.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Gets the usage counters of this pool, every rent is an allocation.
+    pub fn stats(&self) -> PoolStats {
+        let rents = self.counters.rents.load(Ordering::Relaxed);
+        PoolStats {
+            rents,
+            returns: self.counters.returns.load(Ordering::Relaxed),
+            allocations: rents,
+            ..PoolStats::default()
+        }
+    }
+}
+
+impl<T: Default> PassthroughPool<T> {
+    /// Allocate a new array with `minimum_capacity`, each element is
+    /// initialized by the default constructor.
+    pub fn rent(&self, minimum_capacity: usize) -> Result<PassthroughBuffer<T>, ArrayPoolError> {
+        self.rent_with(minimum_capacity, &mut T::default)
+    }
+}
+
+impl<T> Default for PassthroughPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for PassthroughBuffer<T> {
+    fn drop(&mut self) {
+        if self.initialized {
+            unsafe {
+                for elem in self.array.get_ref_mut().iter_mut() {
+                    drop_in_place(elem);
+                }
+            }
+        }
+        self.counters.returns.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<T> Deref for PassthroughBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.array.deref()
+    }
+}
+
+impl<T> DerefMut for PassthroughBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.array.deref_mut()
+    }
+}
+
+impl<T> PoolBackend<T> for PassthroughPool<T> {
+    type Buffer = PassthroughBuffer<T>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.rent_or_create_uninitialized(minimum_capacity, false)
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.rent_or_create_uninitialized(0, false)
+    }
+
+    fn return_buffer(&self, mut buffer: Self::Buffer) {
+        buffer.initialized = false;
+        drop(buffer);
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        PassthroughPool::expand_buffer(self, buffer)
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        PassthroughPool::shrink_buffer(self, buffer)
+    }
+
+    fn min_size(&self) -> usize {
+        PassthroughPool::min_size(self)
+    }
+
+    fn max_size(&self) -> usize {
+        PassthroughPool::max_size(self)
+    }
+}
+
+impl<T, A: Deref<Target = [T]>, B: Deref<Target = [T]>> Deref for EitherBuffer<A, B> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            EitherBuffer::Left(buffer) => buffer,
+            EitherBuffer::Right(buffer) => buffer,
+        }
+    }
+}
+
+impl<T, A: DerefMut<Target = [T]>, B: DerefMut<Target = [T]>> DerefMut for EitherBuffer<A, B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            EitherBuffer::Left(buffer) => buffer,
+            EitherBuffer::Right(buffer) => buffer,
+        }
+    }
+}
+
+impl<T, A: PoolBackend<T>, B: PoolBackend<T>> PoolBackend<T> for EitherPool<A, B> {
+    type Buffer = EitherBuffer<A::Buffer, B::Buffer>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        match self {
+            EitherPool::Left(pool) => pool.rent_uninitialized(minimum_capacity).map(EitherBuffer::Left),
+            EitherPool::Right(pool) => pool.rent_uninitialized(minimum_capacity).map(EitherBuffer::Right),
+        }
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        match self {
+            EitherPool::Left(pool) => pool.rent_minimum_uninitialized().map(EitherBuffer::Left),
+            EitherPool::Right(pool) => pool.rent_minimum_uninitialized().map(EitherBuffer::Right),
+        }
+    }
+
+    fn return_buffer(&self, buffer: Self::Buffer) {
+        match (self, buffer) {
+            (EitherPool::Left(pool), EitherBuffer::Left(buffer)) => pool.return_buffer(buffer),
+            (EitherPool::Right(pool), EitherBuffer::Right(buffer)) => pool.return_buffer(buffer),
+            _ => panic!("buffer was not rented from this pool"),
+        }
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        match (self, buffer) {
+            (EitherPool::Left(pool), EitherBuffer::Left(buffer)) => pool.expand_buffer(buffer).map(EitherBuffer::Left),
+            (EitherPool::Right(pool), EitherBuffer::Right(buffer)) => pool.expand_buffer(buffer).map(EitherBuffer::Right),
+            _ => panic!("buffer was not rented from this pool"),
+        }
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        match (self, buffer) {
+            (EitherPool::Left(pool), EitherBuffer::Left(buffer)) => EitherBuffer::Left(pool.shrink_buffer(buffer)),
+            (EitherPool::Right(pool), EitherBuffer::Right(buffer)) => EitherBuffer::Right(pool.shrink_buffer(buffer)),
+            _ => panic!("buffer was not rented from this pool"),
+        }
+    }
+
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        match (self, buffer) {
+            (EitherPool::Left(pool), EitherBuffer::Left(buffer)) => pool.tag_buffer(buffer, tag),
+            (EitherPool::Right(pool), EitherBuffer::Right(buffer)) => pool.tag_buffer(buffer, tag),
+            _ => panic!("buffer was not rented from this pool"),
+        }
+    }
+
+    fn min_size(&self) -> usize {
+        match self {
+            EitherPool::Left(pool) => pool.min_size(),
+            EitherPool::Right(pool) => pool.min_size(),
+        }
+    }
+
+    fn max_size(&self) -> usize {
+        match self {
+            EitherPool::Left(pool) => pool.max_size(),
+            EitherPool::Right(pool) => pool.max_size(),
+        }
+    }
+}
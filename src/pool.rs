@@ -1,26 +1,228 @@
-use std::collections::BTreeMap;
-use std::fmt::{Display, Formatter};
+use std::borrow::{Borrow, BorrowMut};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::mem::swap;
-use std::ops::{Deref, DerefMut};
-use std::ptr::drop_in_place;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex, Weak};
+use std::ops::{Deref, DerefMut, Index, IndexMut, RangeBounds};
+use std::ptr::{drop_in_place, write_bytes};
+use std::slice::{ChunksExact, ChunksExactMut, SliceIndex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, Weak};
 use std::thread;
 use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{Mutex as ChainMutex, MutexGuard as ChainGuard};
+#[cfg(feature = "parking_lot")]
+use parking_lot::{Mutex as ChainMutex, MutexGuard as ChainGuard};
 
+use crate::allocator::BufferAllocator;
+use crate::config::PoolConfig;
+#[cfg(feature = "backtrace")]
+use crate::diagnostics::{Rental, RentalRegistry};
+use crate::guard::{fill_uninitialized, InitGuard};
+use crate::profiler::PoolEventKind;
+#[cfg(feature = "profiler")]
+use crate::profiler::{EventRing, PoolEvent, RentSite, SiteProfile};
 use crate::raw_buffer::RawBuffer;
+use crate::stats::{Compaction, PoolStats, StatsSnapshot, TagStats, ThreadStats};
 use thread_local::ThreadLocal;
+#[cfg(feature = "zerocopy")]
+use zerocopy::{CastError, FromBytes, Immutable, IntoBytes, KnownLayout};
+
+#[derive(Default)]
+struct LockTimings {
+    contended: AtomicUsize,
+    local_wait: AtomicU64,
+    registry_wait: AtomicU64,
+    steal_time: AtomicU64,
+}
+
+/// Limits shared by every size class of a pool.
+struct PoolLimits {
+    discard_above: AtomicUsize,
+    max_cached_bytes: AtomicUsize,
+    max_cached_per_thread: AtomicUsize,
+    steal_after_misses: AtomicUsize,
+    cached_bytes: AtomicUsize,
+    scrub: AtomicBool,
+    frozen: AtomicBool,
+    alloc_forbidden: ThreadLocal<Cell<bool>>,
+    deterministic: bool,
+    tags: Mutex<HashMap<&'static str, TagStats>>,
+    threads: ThreadLocal<Arc<ThreadCounters>>,
+    #[cfg(feature = "profiler")]
+    events: EventRing,
+    #[cfg(feature = "profiler")]
+    sites: SiteProfile,
+    #[cfg(feature = "backtrace")]
+    rentals: RentalRegistry,
+}
+
+/// Rent counters of the thread owning a slot of `PoolLimits::threads`.
+struct ThreadCounters {
+    owner: Mutex<(ThreadId, Option<String>)>,
+    /// Set once the owner exited, until another thread reuses the slot.
+    exited: AtomicBool,
+    rents: AtomicUsize,
+    hits: AtomicUsize,
+    steals: AtomicUsize,
+    allocations: AtomicUsize,
+}
 
 struct LocalBufferChain<T> {
-    chunk_linked_list: Mutex<Vec<RawBuffer<T>>>,
-    chunk_count: Arc<AtomicUsize>
+    chunk_linked_list: ChainMutex<Vec<RawBuffer<T>>>,
+    chunk_count: Arc<AtomicUsize>,
+    /// Consecutive rents this chain couldn't serve since the last steal.
+    misses: AtomicUsize,
+    /// Set while no live thread has a hook reclaiming this chain on exit.
+    orphaned: AtomicBool,
+}
+
+/// Work run when the current thread exits, see `register_exit_hook`.
+trait ThreadExit {
+    fn on_thread_exit(self: Box<Self>);
+
+    /// Check whether running this hook would do nothing.
+    fn is_stale(&self) -> bool;
+}
+
+#[derive(Default)]
+struct ThreadExitHooks(RefCell<Vec<Box<dyn ThreadExit>>>);
+
+thread_local! {
+    static EXIT_HOOKS: ThreadExitHooks = ThreadExitHooks::default();
+    static ASSIGNED_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Key the chains of the current thread by `id` in pools built with
+/// `PoolConfig::deterministic`, instead of an id picked by the runtime.
+///
+/// Only affects the chains the thread creates afterward, so call it before
+/// the thread first rents from such a pool. Ids should be unique.
+pub fn set_thread_id(id: u64) {
+    ASSIGNED_ID.with(|assigned| assigned.set(Some(id)));
+}
+
+impl Drop for ThreadExitHooks {
+    fn drop(&mut self) {
+        for hook in self.0.get_mut().drain(..) {
+            hook.on_thread_exit();
+        }
+    }
+}
+
+/// Run `hook` when the current thread exits, right away if it is exiting.
+fn register_exit_hook(hook: Box<dyn ThreadExit>) {
+    let mut hook = Some(hook);
+    let _ = EXIT_HOOKS.try_with(|hooks| {
+        let mut hooks = hooks.0.borrow_mut();
+        hooks.retain(|hook| !hook.is_stale());
+        hooks.extend(hook.take());
+    });
+    if let Some(hook) = hook {
+        hook.on_thread_exit();
+    }
+}
+
+/// Moves the arrays cached by a thread's chain to the overflow chain when
+/// the thread exits.
+struct ReclaimLocal<T: Send> {
+    chain: Weak<BufferChain<T>>,
+    local: Weak<LocalBufferChain<T>>,
+}
+
+/// Marks the counters of a thread as exited when it exits.
+struct MarkExited(Weak<ThreadCounters>);
+
+impl ThreadExit for MarkExited {
+    fn on_thread_exit(self: Box<Self>) {
+        if let Some(counters) = self.0.upgrade() {
+            counters.exited.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        self.0.strong_count() == 0
+    }
+}
+
+impl ThreadCounters {
+    fn new() -> Self {
+        let current = thread::current();
+        Self {
+            owner: Mutex::new((current.id(), None)),
+            exited: AtomicBool::new(true),
+            rents: AtomicUsize::default(),
+            hits: AtomicUsize::default(),
+            steals: AtomicUsize::default(),
+            allocations: AtomicUsize::default(),
+        }
+    }
+
+    fn reset(&self) {
+        self.rents.store(0, Ordering::Relaxed);
+        self.hits.store(0, Ordering::Relaxed);
+        self.steals.store(0, Ordering::Relaxed);
+        self.allocations.store(0, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> ThreadStats {
+        let (id, name) = self.owner.lock().unwrap().clone();
+        ThreadStats {
+            id,
+            name,
+            alive: !self.exited.load(Ordering::Relaxed),
+            rents: self.rents.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            steals: self.steals.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T: Send> ThreadExit for ReclaimLocal<T> {
+    fn on_thread_exit(self: Box<Self>) {
+        if let (Some(chain), Some(local)) = (self.chain.upgrade(), self.local.upgrade()) {
+            chain.reclaim(&local);
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        self.chain.strong_count() == 0 || self.local.strong_count() == 0
+    }
 }
 
 struct BufferChain<T: Send>{
     chunk_size: usize,
+    align: usize,
+    allocator: Option<Arc<dyn BufferAllocator>>,
     chunk_count: Arc<AtomicUsize>,
-    chains: Mutex<BTreeMap<u64, Weak<LocalBufferChain<T>>>>,
-    local_chain: ThreadLocal<Arc<LocalBufferChain<T>>>
+    rents: AtomicUsize,
+    returns: AtomicUsize,
+    allocations: AtomicUsize,
+    steals: AtomicUsize,
+    discards: AtomicUsize,
+    evictions: AtomicUsize,
+    /// `rents` and `allocations` as of the last `compact`.
+    compacted_rents: AtomicUsize,
+    compacted_allocations: AtomicUsize,
+    /// Number of arrays currently rented, and its highest value since the
+    /// last reset of the counters.
+    rented: AtomicUsize,
+    peak_rented: AtomicUsize,
+    /// Set once the class is removed from its pool, returned arrays are freed.
+    retired: AtomicBool,
+    limits: Arc<PoolLimits>,
+    timings: LockTimings,
+    chains: ChainMutex<BTreeMap<u64, Weak<LocalBufferChain<T>>>>,
+    /// Key of the chain the next steal starts looking from.
+    next_victim: AtomicU64,
+    local_chain: ThreadLocal<Arc<LocalBufferChain<T>>>,
+    /// Arrays of exited threads and arrays returned past the per-thread cap.
+    overflow: ChainMutex<Vec<RawBuffer<T>>>,
+    cpu_chains: Vec<Arc<LocalBufferChain<T>>>
 }
 
 /// Represent a borrowed array.
@@ -28,19 +230,172 @@ pub struct BorrowingSlice<T: Send>{
     array: RawBuffer<T>,
     chain: Arc<BufferChain<T>>,
     pub(crate) initialized: bool,
+    tag: Option<&'static str>,
+    /// Id of this rental in `PoolLimits::rentals`, 0 for empty arrays.
+    #[cfg(feature = "backtrace")]
+    rental: u64,
 }
 
+// Like std mutexes, which poison instead: no user code runs while a chain
+// is locked, so a panic never leaves one half-updated
+#[cfg(feature = "parking_lot")]
+impl<T> std::panic::RefUnwindSafe for LocalBufferChain<T> {}
+#[cfg(feature = "parking_lot")]
+impl<T: Send> std::panic::RefUnwindSafe for BufferChain<T> {}
+
 impl<T> Drop for LocalBufferChain<T>{
     fn drop(&mut self) {
-        let locked = self.chunk_linked_list.lock().unwrap();
-        let len = locked.len();
+        let len = lock_chain(&self.chunk_linked_list).len();
         self.chunk_count.fetch_sub(len, Ordering::SeqCst);
     }
 }
 
+#[cfg(not(feature = "parking_lot"))]
+fn lock_chain<V>(mutex: &ChainMutex<V>) -> ChainGuard<'_, V> {
+    mutex.lock().unwrap()
+}
+
+#[cfg(feature = "parking_lot")]
+fn lock_chain<V>(mutex: &ChainMutex<V>) -> ChainGuard<'_, V> {
+    mutex.lock()
+}
+
+/// Lock `mutex` if no other thread holds it.
+#[cfg(all(feature = "stats-timing", not(feature = "parking_lot")))]
+fn try_lock_chain<V>(mutex: &ChainMutex<V>) -> Option<ChainGuard<'_, V>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+        Err(std::sync::TryLockError::Poisoned(poisoned)) => panic!("{poisoned}"),
+    }
+}
+
+#[cfg(all(feature = "stats-timing", feature = "parking_lot"))]
+fn try_lock_chain<V>(mutex: &ChainMutex<V>) -> Option<ChainGuard<'_, V>> {
+    mutex.try_lock()
+}
+
+impl LockTimings {
+    #[cfg(feature = "stats-timing")]
+    fn lock<'a, V>(&self, mutex: &'a ChainMutex<V>, wait: &AtomicU64) -> ChainGuard<'a, V> {
+        if let Some(guard) = try_lock_chain(mutex) { return guard; }
+        let start = Instant::now();
+        let guard = lock_chain(mutex);
+        self.contended.fetch_add(1, Ordering::Relaxed);
+        wait.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        guard
+    }
+
+    #[cfg(not(feature = "stats-timing"))]
+    fn lock<'a, V>(&self, mutex: &'a ChainMutex<V>, _wait: &AtomicU64) -> ChainGuard<'a, V> {
+        lock_chain(mutex)
+    }
+
+    fn lock_local<'a, V>(&self, mutex: &'a ChainMutex<V>) -> ChainGuard<'a, V> {
+        self.lock(mutex, &self.local_wait)
+    }
+
+    fn lock_registry<'a, V>(&self, mutex: &'a ChainMutex<V>) -> ChainGuard<'a, V> {
+        self.lock(mutex, &self.registry_wait)
+    }
+
+    /// Run `steal`, adding the time it took to the steal timings.
+    #[cfg(feature = "stats-timing")]
+    fn time_steal<R, F: FnOnce() -> R>(&self, steal: F) -> R {
+        let start = Instant::now();
+        let result = steal();
+        self.steal_time.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        result
+    }
+
+    #[cfg(not(feature = "stats-timing"))]
+    fn time_steal<R, F: FnOnce() -> R>(&self, steal: F) -> R {
+        steal()
+    }
+}
+
+impl PoolLimits {
+    fn new(config: &PoolConfig) -> Arc<Self> {
+        Arc::new(Self {
+            discard_above: AtomicUsize::new(config.discard_above.unwrap_or(usize::MAX)),
+            max_cached_bytes: AtomicUsize::new(config.max_cached_bytes.unwrap_or(usize::MAX)),
+            max_cached_per_thread: AtomicUsize::new(config.max_cached_per_thread.unwrap_or(usize::MAX)),
+            steal_after_misses: AtomicUsize::new(config.steal_after_misses),
+            cached_bytes: AtomicUsize::default(),
+            scrub: AtomicBool::new(config.scrub),
+            frozen: AtomicBool::default(),
+            alloc_forbidden: ThreadLocal::new(),
+            deterministic: config.deterministic,
+            tags: Mutex::new(HashMap::new()),
+            threads: ThreadLocal::new(),
+            #[cfg(feature = "profiler")]
+            events: EventRing::new(),
+            #[cfg(feature = "profiler")]
+            sites: SiteProfile::new(),
+            #[cfg(feature = "backtrace")]
+            rentals: RentalRegistry::new(),
+        })
+    }
+
+    fn set_alloc_forbidden(&self, forbidden: bool) {
+        self.alloc_forbidden.get_or(|| Cell::new(false)).set(forbidden);
+    }
+
+    fn alloc_forbidden(&self) -> bool {
+        self.alloc_forbidden.get().is_some_and(Cell::get)
+    }
+
+    /// Reserve room for caching an array of `bytes`, fails if the array
+    /// should be freed instead.
+    fn admit(&self, bytes: usize) -> bool {
+        if bytes >= self.discard_above.load(Ordering::Relaxed) { return false; }
+        let max = self.max_cached_bytes.load(Ordering::Relaxed);
+        self.cached_bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cached| {
+                cached.checked_add(bytes).filter(|total| *total <= max)
+            })
+            .is_ok()
+    }
+
+    /// Give back room reserved by `admit`.
+    fn release(&self, bytes: usize) {
+        self.cached_bytes.fetch_sub(bytes, Ordering::AcqRel);
+    }
+
+    /// Gets the rent counters of the current thread.
+    fn thread_counters(&self) -> &ThreadCounters {
+        let counters = self.threads.get_or(|| Arc::new(ThreadCounters::new()));
+        // Set for new counters, or those left behind by an exited thread whose slot this thread reuses
+        if counters.exited.load(Ordering::Relaxed) && counters.exited.swap(false, Ordering::Relaxed) {
+            let current = thread::current();
+            *counters.owner.lock().unwrap() = (current.id(), current.name().map(str::to_string));
+            counters.reset();
+            register_exit_hook(Box::new(MarkExited(Arc::downgrade(counters))));
+        }
+        counters
+    }
+
+    /// Count an array of `bytes` rented under `tag`.
+    fn tag_rented(&self, tag: &'static str, bytes: usize) {
+        let mut tags = self.tags.lock().unwrap();
+        let stats = tags.entry(tag).or_default();
+        stats.rents += 1;
+        stats.held += 1;
+        stats.held_bytes += bytes;
+    }
+
+    /// Count an array of `bytes` rented under `tag` coming back.
+    fn tag_returned(&self, tag: &'static str, bytes: usize) {
+        let mut tags = self.tags.lock().unwrap();
+        let stats = tags.get_mut(tag).unwrap();
+        stats.held -= 1;
+        stats.held_bytes -= bytes;
+    }
+}
+
 impl<T> LocalBufferChain<T>{
-    pub unsafe fn borrow(self: &Arc<Self>) -> Option<RawBuffer<T>>{
-        let mut lock_guard = self.chunk_linked_list.lock().unwrap();
+    pub unsafe fn borrow(self: &Arc<Self>, timings: &LockTimings) -> Option<RawBuffer<T>>{
+        let mut lock_guard = timings.lock_local(&self.chunk_linked_list);
         if let Some(slice) = lock_guard.pop() {
             self.chunk_count.fetch_sub(1, Ordering::SeqCst);
             Some(slice)
@@ -48,60 +403,208 @@ impl<T> LocalBufferChain<T>{
     }
 }
 
+/// Selects how the cached arrays of each size class are split into chains.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Sharding {
+    /// Every thread owns a chain. Best when threads are long-lived.
+    #[default]
+    PerThread,
+    /// Every CPU core owns a chain, picked by the core the calling thread
+    /// currently runs on (falling back to its thread id when the core can't
+    /// be queried). Best when many short-lived threads share the pool.
+    PerCpu,
+}
+
 /// Provides a resource pool that enables reusing instances of type `T`.
 pub struct ArrayPool<T: Send> {
+    config: PoolConfig,
+    resets: AtomicUsize,
+    limits: Arc<PoolLimits>,
     empty_chain: Arc<BufferChain<T>>,
-    chunk_map: BTreeMap<usize, Arc<BufferChain<T>>>
+    chunk_map: RwLock<BTreeMap<usize, Arc<BufferChain<T>>>>
+}
+
+fn thread_key() -> u64 {
+    let tid = thread::current().id();
+    unsafe { *(&tid as *const ThreadId as *const u64) }
+}
+
+#[cfg(target_os = "linux")]
+fn current_cpu() -> usize {
+    extern "C" {
+        fn sched_getcpu() -> std::os::raw::c_int;
+    }
+    let cpu = unsafe { sched_getcpu() };
+    if cpu >= 0 { cpu as usize } else { thread_key() as usize }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_cpu() -> usize {
+    thread_key() as usize
 }
 
 impl<T: Send> BufferChain<T>{
-    /// Create a new buffer chain with specified power.
-    pub fn new(size_power: u8) -> Arc<Self> {
+    /// Create a new buffer chain of arrays with `chunk_size` elements.
+    pub fn new(chunk_size: usize, align: usize, allocator: Option<Arc<dyn BufferAllocator>>, sharding: Sharding, limits: Arc<PoolLimits>) -> Arc<Self> {
+        let chunk_count = Arc::new(AtomicUsize::default());
+        let mut chains = BTreeMap::new();
+        let mut cpu_chains = vec![];
+        if sharding == Sharding::PerCpu {
+            let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            for core in 0..cores {
+                let chain = Arc::new(LocalBufferChain {
+                    chunk_linked_list: ChainMutex::new(vec![]),
+                    chunk_count: chunk_count.clone(),
+                    misses: AtomicUsize::default(),
+                    orphaned: AtomicBool::new(false),
+                });
+                chains.insert(core as u64, Arc::downgrade(&chain));
+                cpu_chains.push(chain);
+            }
+        }
         Arc::new(Self {
-            chunk_size: 1usize << size_power,
-            chunk_count: Arc::new(AtomicUsize::default()),
-            chains: Mutex::new(BTreeMap::new()),
+            chunk_size,
+            align,
+            allocator,
+            chunk_count,
+            rents: AtomicUsize::default(),
+            returns: AtomicUsize::default(),
+            allocations: AtomicUsize::default(),
+            steals: AtomicUsize::default(),
+            discards: AtomicUsize::default(),
+            evictions: AtomicUsize::default(),
+            compacted_rents: AtomicUsize::default(),
+            compacted_allocations: AtomicUsize::default(),
+            rented: AtomicUsize::default(),
+            peak_rented: AtomicUsize::default(),
+            retired: AtomicBool::default(),
+            limits,
+            timings: LockTimings::default(),
+            chains: ChainMutex::new(chains),
+            next_victim: AtomicU64::default(),
             local_chain: ThreadLocal::new(),
+            overflow: ChainMutex::new(vec![]),
+            cpu_chains,
         })
     }
 
-    fn new_array<F: FnMut() -> T>(&self, fabricator: &mut F) -> RawBuffer<T> {
-        unsafe {
-            let mut buffer = RawBuffer::<T>::new(self.chunk_size, false);
-            let length = buffer.len();
-            let reference = buffer.get_ref_mut();
-            for i in 0..length{
-                // Avoid dropping the old, invalid value
-                std::ptr::write(&mut reference[i], fabricator());
-            }
-
-            buffer
+    fn get_local(self: &Arc<Self>) -> &Arc<LocalBufferChain<T>> {
+        if !self.cpu_chains.is_empty() {
+            return &self.cpu_chains[current_cpu() % self.cpu_chains.len()];
         }
-    }
-
-    fn get_local(&self) -> &Arc<LocalBufferChain<T>> {
         let arc_count = self.chunk_count.clone();
-        self.local_chain.get_or(move ||{
+        let local = self.local_chain.get_or(move ||{
             let arc = Arc::new(LocalBufferChain {
-                chunk_linked_list: Mutex::new(vec![]),
+                chunk_linked_list: ChainMutex::new(vec![]),
                 chunk_count: arc_count,
+                misses: AtomicUsize::default(),
+                orphaned: AtomicBool::new(true),
             });
-            let mut lock_guard = self.chains.lock().unwrap();
-            let tid = thread::current().id();
-            lock_guard.insert(unsafe { *(&tid as *const ThreadId as *const u64) }, Arc::downgrade(&arc));
+            let mut lock_guard = self.timings.lock_registry(&self.chains);
+            lock_guard.insert(self.chain_key(), Arc::downgrade(&arc));
 
             arc
-        })
+        });
+        // Set for a new chain, or one left behind by an exited thread whose slot this thread reuses
+        if local.orphaned.load(Ordering::Relaxed) && local.orphaned.swap(false, Ordering::Relaxed) {
+            if self.limits.deterministic {
+                // Key a reused chain by the id of its new thread
+                let mut lock_guard = self.timings.lock_registry(&self.chains);
+                lock_guard.retain(|_, chain| !Weak::ptr_eq(chain, &Arc::downgrade(local)));
+                lock_guard.insert(self.chain_key(), Arc::downgrade(local));
+            }
+            let hook: Box<dyn ThreadExit + '_> = Box::new(ReclaimLocal {
+                chain: Arc::downgrade(self),
+                local: Arc::downgrade(local),
+            });
+            // The hook only holds weak pointers, it can't reach `T` once the pool is gone
+            register_exit_hook(unsafe { std::mem::transmute::<Box<dyn ThreadExit + '_>, Box<dyn ThreadExit>>(hook) });
+        }
+        local
+    }
+
+    /// Move the arrays cached by `local`, whose thread exited, to the
+    /// overflow chain.
+    fn reclaim(&self, local: &LocalBufferChain<T>) {
+        let reclaimed = std::mem::take(&mut *self.timings.lock_local(&local.chunk_linked_list));
+        self.timings.lock_local(&self.overflow).extend(reclaimed);
+        local.orphaned.store(true, Ordering::Relaxed);
+    }
+
+    fn borrow_overflow(&self) -> Option<RawBuffer<T>> {
+        let cached = self.timings.lock_local(&self.overflow).pop()?;
+        self.chunk_count.fetch_sub(1, Ordering::SeqCst);
+        Some(cached)
+    }
+
+    /// Gets the key of the current thread's chain in the registry.
+    fn chain_key(&self) -> u64 {
+        match self.limits.deterministic {
+            true => ASSIGNED_ID.with(Cell::get).unwrap_or_else(thread_key),
+            false => thread_key(),
+        }
+    }
+
+    /// Count a miss of `local`, steal from other chains once there were
+    /// enough consecutive misses.
+    fn steal_after_miss(&self, local: &LocalBufferChain<T>) -> Option<RawBuffer<T>> {
+        let misses = local.misses.fetch_add(1, Ordering::Relaxed);
+        if misses < self.limits.steal_after_misses.load(Ordering::Relaxed) { return None; }
+        local.misses.store(0, Ordering::Relaxed);
+        self.borrow_from_other_chains()
     }
 
     fn borrow_from_other_chains(&self) -> Option<RawBuffer<T>> {
-        let mut lock_guard = self.chains.lock().unwrap();
+        let started = self.event_start();
+        let stolen = self.timings.time_steal(|| self.steal_from_other_chains());
+        self.record_event(PoolEventKind::Steal, started);
+        stolen
+    }
+
+    /// Gets the start of an operation if the pool is profiling.
+    #[cfg(feature = "profiler")]
+    fn event_start(&self) -> Option<Instant> {
+        self.limits.events.start()
+    }
+
+    #[cfg(not(feature = "profiler"))]
+    fn event_start(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Record an operation that began at `started`, see `event_start`.
+    #[cfg(feature = "profiler")]
+    fn record_event(&self, kind: PoolEventKind, started: Option<Instant>) {
+        self.limits.events.record(kind, self.chunk_size, started);
+    }
+
+    #[cfg(not(feature = "profiler"))]
+    fn record_event(&self, _kind: PoolEventKind, _started: Option<Instant>) {}
+
+    /// Count a rent against its call site if the pool is profiling sites.
+    #[cfg(feature = "profiler")]
+    fn record_site(&self, allocated: bool) {
+        self.limits.sites.record(if allocated { self.chunk_bytes() } else { 0 });
+    }
+
+    #[cfg(not(feature = "profiler"))]
+    fn record_site(&self, _allocated: bool) {}
+
+    fn steal_from_other_chains(&self) -> Option<RawBuffer<T>> {
+        let mut lock_guard = self.timings.lock_registry(&self.chains);
         let mut remove_queue: Vec<u64> = Vec::new();
         let mut found: Option<RawBuffer<T>> = None;
 
-        for (id, chain_weak) in lock_guard.iter() {
+        // Resume after the last victim so stealing spreads across chains
+        let start = match self.limits.deterministic {
+            true => 0,
+            false => self.next_victim.load(Ordering::Relaxed),
+        };
+        for (id, chain_weak) in lock_guard.range(start..).chain(lock_guard.range(..start)) {
             if let Some(chain) = chain_weak.upgrade() {
-                if let Some(cached) = unsafe{ chain.borrow() }{
+                if let Some(cached) = unsafe{ chain.borrow(&self.timings) }{
+                    self.steals.fetch_add(1, Ordering::Relaxed);
+                    self.next_victim.store(id.wrapping_add(1), Ordering::Relaxed);
                     found = Some(cached);
                     break;
                 }
@@ -117,61 +620,285 @@ impl<T: Send> BufferChain<T>{
         found
     }
 
-    /// Rent a new array.
-    ///
-    /// If none is available for renting, create a new one with each element
-    /// initialized by `fabricator`.
-    pub fn rent_with<F: FnMut() -> T>(self: &Arc<Self>, fabricator: &mut F) -> BorrowingSlice<T> {
-        let local_chain = self.get_local();
-        let array;
-        if self.chunk_count.load(Ordering::Acquire) == 0 {
-            array = self.new_array(fabricator);
-        } else if let Some(cached) = unsafe{ local_chain.borrow() }{
-            array = cached;
-        } else if let Some(cached) = self.borrow_from_other_chains() {
-            array = cached
-        } else {
-            array = self.new_array(fabricator);
-        }
-        BorrowingSlice{
-            array,
-            chain: self.clone(),
-            initialized: true,
+    fn chunk_bytes(&self) -> usize {
+        self.chunk_size * size_of::<T>()
+    }
+
+    /// Release the room taken by a cached array that is being rented.
+    fn uncache(&self, array: RawBuffer<T>) -> RawBuffer<T> {
+        self.limits.release(self.chunk_bytes());
+        array
+    }
+
+    /// Rent a new array with each element initialized by `fabricator`.
+    pub fn rent_with<F: FnMut() -> T>(self: &Arc<Self>, fabricator: &mut F) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        let mut slice = unsafe { self.rent_or_create_uninitialized(false) }?;
+        // Cached arrays had their elements dropped on return, avoid dropping them again
+        let mut guard = unsafe { InitGuard::new(&mut slice) };
+        for _ in 0..self.chunk_size {
+            guard.push(fabricator());
         }
+        guard.finish();
+        slice.initialized = true;
+        Ok(slice)
     }
 
     /// Create a new uninitialized array. Zero the array if needed.
     pub(crate) unsafe fn new_uninitialized(&self, zeroed: bool) -> RawBuffer<T> {
-        RawBuffer::new(self.chunk_size, zeroed)
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        let started = self.event_start();
+        let array = RawBuffer::with_allocator(self.chunk_size, zeroed, self.align, self.allocator.as_ref());
+        self.record_event(PoolEventKind::Allocation, started);
+        array
+    }
+
+    /// Create a new uninitialized array unless the current thread is
+    /// forbidden to allocate, panic in that case on debug builds.
+    unsafe fn allocate(&self, zeroed: bool) -> Result<RawBuffer<T>, ArrayPoolError> {
+        if self.limits.frozen.load(Ordering::Relaxed) {
+            return Err(ArrayPoolError::Exhausted);
+        }
+        if self.limits.alloc_forbidden() {
+            if cfg!(debug_assertions) {
+                panic!("array pool allocation of {} elements on thread {:?}, which forbids allocating",
+                       self.chunk_size, thread::current().name().unwrap_or("<unnamed>"));
+            }
+            return Err(ArrayPoolError::AllocationForbidden);
+        }
+        Ok(self.new_uninitialized(zeroed))
     }
 
     /// Rent a new array.
     ///
     /// If none is available for renting, create a new one without initialize it,
     /// zero if needed.
-    pub unsafe fn rent_or_create_uninitialized(self: &Arc<Self>, zeroed: bool) -> BorrowingSlice<T>{
+    pub unsafe fn rent_or_create_uninitialized(self: &Arc<Self>, zeroed: bool) -> Result<BorrowingSlice<T>, ArrayPoolError>{
+        let started = self.event_start();
         let local_chain = self.get_local();
+        let counters = self.limits.thread_counters();
         let array;
-        if self.chunk_count.load(Ordering::Acquire) == 0 {
-            array = self.new_uninitialized(zeroed);
-        } else if let Some(cached) = local_chain.borrow(){
-            array = cached;
-        } else if let Some(cached) = self.borrow_from_other_chains() {
-            array = cached
+        let mut allocated = false;
+        if !self.limits.deterministic && self.chunk_count.load(Ordering::Acquire) == 0 {
+            allocated = true;
+            array = self.allocate(zeroed)?;
+        } else if let Some(cached) = local_chain.borrow(&self.timings){
+            local_chain.misses.store(0, Ordering::Relaxed);
+            counters.hits.fetch_add(1, Ordering::Relaxed);
+            array = self.uncache(cached);
+        } else if let Some(cached) = self.borrow_overflow() {
+            array = self.uncache(cached);
+        } else if let Some(cached) = self.steal_after_miss(local_chain) {
+            counters.steals.fetch_add(1, Ordering::Relaxed);
+            array = self.uncache(cached);
         } else {
-            array = self.new_uninitialized(zeroed);
+            allocated = true;
+            array = self.allocate(zeroed)?;
         }
-        BorrowingSlice{
+        if allocated {
+            counters.allocations.fetch_add(1, Ordering::Relaxed);
+        }
+        counters.rents.fetch_add(1, Ordering::Relaxed);
+        self.count_rent();
+        self.record_event(PoolEventKind::Rent, started);
+        self.record_site(allocated);
+        Ok(BorrowingSlice{
+            #[cfg(feature = "backtrace")]
+            rental: self.limits.rentals.rent(array.as_ptr() as usize, self.chunk_size, self.chunk_bytes(), allocated),
             array,
             chain: self.clone(),
             initialized: false,
+            tag: None,
+        })
+    }
+
+    /// Free cached arrays from every thread's chain as long as `should_evict` holds.
+    fn evict_while<F: FnMut() -> bool>(&self, mut should_evict: F) {
+        while should_evict() {
+            match self.borrow_overflow() {
+                Some(cached) => self.evict(cached),
+                None => break,
+            }
+        }
+        let lock_guard = self.timings.lock_registry(&self.chains);
+        for chain in lock_guard.values().filter_map(Weak::upgrade) {
+            while should_evict() {
+                match unsafe { chain.borrow(&self.timings) } {
+                    Some(cached) => self.evict(cached),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Free `cached`, taken out of a chain.
+    fn evict(&self, cached: RawBuffer<T>) {
+        let started = self.event_start();
+        drop(self.uncache(cached));
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+        self.record_event(PoolEventKind::Eviction, started);
+    }
+
+    /// Move the arrays cached by `other` into the overflow chain, as long
+    /// as the limits of this pool admit them.
+    fn absorb(&self, other: &Self) -> usize {
+        let mut absorbed = vec![];
+        // Reserve room before taking an array so none has to be put back
+        let mut take = |borrow: &mut dyn FnMut() -> Option<RawBuffer<T>>| loop {
+            if !self.limits.admit(self.chunk_bytes()) { return false; }
+            match borrow() {
+                Some(cached) => absorbed.push(other.uncache(cached)),
+                None => {
+                    self.limits.release(self.chunk_bytes());
+                    return true;
+                }
+            }
+        };
+        if take(&mut || other.borrow_overflow()) {
+            let lock_guard = other.timings.lock_registry(&other.chains);
+            for chain in lock_guard.values().filter_map(Weak::upgrade) {
+                if !take(&mut || unsafe { chain.borrow(&other.timings) }) { break; }
+            }
+        }
+        let count = absorbed.len();
+        self.timings.lock_local(&self.overflow).extend(absorbed);
+        self.chunk_count.fetch_add(count, Ordering::SeqCst);
+        count
+    }
+
+    /// Cache `array` in the current thread's chain, or in the overflow
+    /// chain if the thread already caches as many arrays as allowed.
+    fn push_cached(self: &Arc<Self>, array: RawBuffer<T>) {
+        let mut lock_guard = self.timings.lock_local(&self.get_local().chunk_linked_list);
+        if lock_guard.len() >= self.limits.max_cached_per_thread.load(Ordering::Relaxed) {
+            drop(lock_guard);
+            lock_guard = self.timings.lock_local(&self.overflow);
+        }
+        // Checked under the lock, so either `retire_size_class` evicts the
+        // array or the array sees the class retired
+        if self.retired.load(Ordering::Acquire) {
+            drop(lock_guard);
+            self.limits.release(self.chunk_bytes());
+            self.discards.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        lock_guard.push(array);
+        self.chunk_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Cache `count` new arrays, see `push_cached`.
+    fn prewarm(self: &Arc<Self>, count: usize) {
+        for _ in 0..count {
+            if !self.limits.admit(self.chunk_bytes()) { break; }
+            self.push_cached(unsafe { self.new_uninitialized(false) });
+            // Arrays allocated ahead are not misses of the next compaction
+            self.compacted_allocations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Cache `array`, carved out of a backing region, if the limits of
+    /// the pool admit it.
+    fn cache_region(self: &Arc<Self>, array: RawBuffer<T>) {
+        if self.limits.admit(self.chunk_bytes()) {
+            self.push_cached(array);
+        }
+    }
+
+    /// Cache as many arrays as the rents since the last compaction, or
+    /// allocate one more for each of its misses. New arrays go to the
+    /// overflow chain, where every thread finds them.
+    fn compact(&self) -> Compaction {
+        let rents = self.rents.load(Ordering::Relaxed);
+        let allocations = self.allocations.load(Ordering::Relaxed);
+        let recent_rents = rents.saturating_sub(self.compacted_rents.swap(rents, Ordering::Relaxed));
+        let misses = allocations.saturating_sub(self.compacted_allocations.swap(allocations, Ordering::Relaxed));
+        let cached = self.chunk_count.load(Ordering::Acquire);
+        let mut compaction = Compaction::default();
+        if misses > 0 {
+            if self.limits.alloc_forbidden() { return compaction; }
+            for _ in cached..misses {
+                if !self.limits.admit(self.chunk_bytes()) { break; }
+                let array = unsafe { self.new_uninitialized(false) };
+                self.timings.lock_local(&self.overflow).push(array);
+                self.chunk_count.fetch_add(1, Ordering::SeqCst);
+                compaction.allocated += 1;
+            }
+            // Arrays allocated here are not misses of the next compaction
+            self.compacted_allocations.fetch_add(compaction.allocated, Ordering::Relaxed);
+        } else if cached > recent_rents {
+            let evictions = self.evictions.load(Ordering::Relaxed);
+            self.evict_while(|| self.chunk_count.load(Ordering::Acquire) > recent_rents);
+            compaction.freed = self.evictions.load(Ordering::Relaxed) - evictions;
+        }
+        compaction
+    }
+
+    fn count_rent(&self) {
+        self.rents.fetch_add(1, Ordering::Relaxed);
+        let rented = self.rented.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_rented.fetch_max(rented, Ordering::Relaxed);
+    }
+
+    /// Zero the usage counters of this chain.
+    fn reset_stats(&self) {
+        self.peak_rented.store(self.rented.load(Ordering::Relaxed), Ordering::Relaxed);
+        for counter in [&self.rents, &self.returns, &self.allocations, &self.steals, &self.discards, &self.evictions, &self.compacted_rents, &self.compacted_allocations, &self.timings.contended] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        for wait in [&self.timings.local_wait, &self.timings.registry_wait, &self.timings.steal_time] {
+            wait.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Gets the usage counters of this chain.
+    pub fn stats(&self) -> PoolStats {
+        let cached = self.chunk_count.load(Ordering::Acquire);
+        PoolStats {
+            rents: self.rents.load(Ordering::Relaxed),
+            returns: self.returns.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            steals: self.steals.load(Ordering::Relaxed),
+            discards: self.discards.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            cached,
+            cached_bytes: cached * self.chunk_bytes(),
+            contended: self.timings.contended.load(Ordering::Relaxed),
+            local_wait: Duration::from_nanos(self.timings.local_wait.load(Ordering::Relaxed)),
+            registry_wait: Duration::from_nanos(self.timings.registry_wait.load(Ordering::Relaxed)),
+            steal_time: Duration::from_nanos(self.timings.steal_time.load(Ordering::Relaxed)),
         }
     }
 }
 
+impl<T: Send> Debug for BufferChain<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let stats = self.stats();
+        f.debug_struct("BufferChain")
+            .field("chunk_size", &self.chunk_size)
+            .field("cached", &stats.cached)
+            .field("rents", &stats.rents)
+            .field("returns", &stats.returns)
+            .field("allocations", &stats.allocations)
+            .field("steals", &stats.steals)
+            .finish()
+    }
+}
+
 impl<T: Send> Drop for BorrowingSlice<T>{
     fn drop(&mut self) {
         if self.array.is_empty() { return; }
+        let started = self.chain.event_start();
+        if let Some(tag) = self.tag {
+            self.chain.limits.tag_returned(tag, self.chain.chunk_bytes());
+        }
+        #[cfg(feature = "backtrace")]
+        self.chain.limits.rentals.release(self.rental);
+        self.return_array();
+        self.chain.record_event(PoolEventKind::Return, started);
+    }
+}
+
+impl<T: Send> BorrowingSlice<T> {
+    fn return_array(&mut self) {
         if self.initialized {
             unsafe {
                 for i in 0..self.len() {
@@ -180,11 +907,72 @@ impl<T: Send> Drop for BorrowingSlice<T>{
                 }
             }
         }
-        let mut lock_guard = self.chain.get_local().chunk_linked_list.lock().unwrap();
+        self.chain.returns.fetch_add(1, Ordering::Relaxed);
+        self.chain.rented.fetch_sub(1, Ordering::Relaxed);
+        if self.chain.retired.load(Ordering::Acquire) || !self.chain.limits.admit(self.chain.chunk_bytes()) {
+            // Free the array right away
+            self.array = RawBuffer::empty();
+            self.chain.discards.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if self.chain.limits.scrub.load(Ordering::Relaxed) {
+            unsafe { write_bytes(self.array.as_mut_ptr(), 0, self.array.len()); }
+        }
         let mut store = RawBuffer::<T>::empty();
         swap(&mut store, &mut self.array);
-        lock_guard.push(store);
-        self.chain.chunk_count.fetch_add(1, Ordering::SeqCst);
+        self.chain.push_cached(store);
+    }
+}
+
+impl<T: Send> BorrowingSlice<T> {
+    /// Move this array out, leaving an empty array in its place.
+    pub fn take(&mut self) -> BorrowingSlice<T> {
+        let empty = BorrowingSlice{
+            array: RawBuffer::empty(),
+            chain: self.chain.clone(),
+            initialized: true,
+            tag: None,
+            #[cfg(feature = "backtrace")]
+            rental: 0,
+        };
+        self.replace(empty)
+    }
+
+    /// Gets the tag this array was rented under.
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// Attribute this array to `tag` in `ArrayPool::tag_stats`, instead of
+    /// the tag it was rented under if any. Empty arrays are never tagged.
+    pub fn set_tag(&mut self, tag: &'static str) {
+        if self.array.is_empty() { return; }
+        let bytes = self.chain.chunk_bytes();
+        if let Some(previous) = self.tag {
+            self.chain.limits.tag_returned(previous, bytes);
+        }
+        self.chain.limits.tag_rented(tag, bytes);
+        self.tag = Some(tag);
+    }
+
+    /// Move this array out, leaving `other` in its place.
+    pub fn replace(&mut self, other: BorrowingSlice<T>) -> BorrowingSlice<T> {
+        std::mem::replace(self, other)
+    }
+}
+
+impl BorrowingSlice<u8> {
+    /// Split this array into frames of `frame_len` bytes, the bytes past
+    /// the last whole frame are available through `remainder`.
+    ///
+    /// Panics if `frame_len` is 0.
+    pub fn frames(&self, frame_len: usize) -> ChunksExact<'_, u8> {
+        self.chunks_exact(frame_len)
+    }
+
+    /// Split this array into mutable frames of `frame_len` bytes, see `frames`.
+    pub fn frames_mut(&mut self, frame_len: usize) -> ChunksExactMut<'_, u8> {
+        self.chunks_exact_mut(frame_len)
     }
 }
 
@@ -201,6 +989,44 @@ impl<T: Send> DerefMut for BorrowingSlice<T>{
     }
 }
 
+impl<T: Send> AsRef<[T]> for BorrowingSlice<T> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: Send> AsMut<[T]> for BorrowingSlice<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T: Send> Borrow<[T]> for BorrowingSlice<T> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: Send> BorrowMut<[T]> for BorrowingSlice<T> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T: Send, I: SliceIndex<[T]>> Index<I> for BorrowingSlice<T> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.deref()[index]
+    }
+}
+
+impl<T: Send, I: SliceIndex<[T]>> IndexMut<I> for BorrowingSlice<T> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.deref_mut()[index]
+    }
+}
+
 impl<T: Send + Display> Display for BorrowingSlice<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "[ ")?;
@@ -220,142 +1046,419 @@ impl<T: Send + Display> Display for BorrowingSlice<T> {
     }
 }
 
-impl<T: Send + Clone> Clone for BorrowingSlice<T> {
-    fn clone(&self) -> Self {
-        let mut new_buffer: RawBuffer<T>;
-        unsafe {
-            new_buffer = match self.chain.get_local().borrow(){
-                Some(v) => v,
-                None => self.chain.new_uninitialized(false)
+impl<T: Send> BorrowingSlice<T> {
+    /// Rent an uninitialized array of the same size class and tag, or
+    /// leave an empty array empty.
+    fn rent_sibling(&self) -> Self {
+        if self.array.is_empty() {
+            // Taken and `rent_empty` arrays hold no elements to clone
+            return BorrowingSlice{
+                array: RawBuffer::empty(),
+                chain: self.chain.clone(),
+                initialized: true,
+                tag: None,
+                #[cfg(feature = "backtrace")]
+                rental: 0,
             };
-            for i in 0..self.len(){
-                // ptr contain uninitialized value
-                std::ptr::write(&mut new_buffer[i], self[i].clone());
+        }
+        let (new_buffer, allocated) = unsafe {
+            match self.chain.get_local().borrow(&self.chain.timings){
+                Some(v) => (self.chain.uncache(v), false),
+                None => (self.chain.allocate(false).expect("Could not allocate a buffer"), true)
             }
+        };
+        self.chain.count_rent();
+        #[cfg(feature = "backtrace")]
+        let rental = self.chain.limits.rentals.rent(new_buffer.as_ptr() as usize, self.chain.chunk_size, self.chain.chunk_bytes(), allocated);
+        self.chain.record_site(allocated);
+        // Returned uninitialized if a clone panics, after dropping the cloned prefix
+        let mut slice = Self{
+            array: new_buffer,
+            chain: self.chain.clone(),
+            initialized: false,
+            tag: None,
+            #[cfg(feature = "backtrace")]
+            rental,
+        };
+        if let Some(tag) = self.tag {
+            slice.set_tag(tag);
         }
+        slice
+    }
 
+    /// Clone this array with a single memcpy, instead of cloning its
+    /// elements one by one like `clone`.
+    pub fn clone_copy(&self) -> Self where T: Copy {
+        let mut slice = self.rent_sibling();
+        unsafe { std::ptr::copy_nonoverlapping(self.array.as_ptr(), slice.array.as_mut_ptr(), self.len()); }
+        slice.initialized = self.initialized;
+        slice
+    }
 
-        Self{
-            array: new_buffer,
-            chain: self.chain.clone(),
-            initialized: true,
+    /// Copy all elements of `src` into this array with a single memcpy,
+    /// leaving it initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` doesn't have the length of this array.
+    pub fn copy_from_slice(&mut self, src: &[T]) where T: Copy {
+        assert_eq!(src.len(), self.len(), "source length does not match the array length");
+        unsafe { std::ptr::copy_nonoverlapping(src.as_ptr(), self.array.as_mut_ptr(), src.len()); }
+        self.initialized = true;
+    }
+
+    /// Copy the elements in `src` to `dest` within this array with a
+    /// single memmove, see `slice::copy_within`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range falls outside of this array.
+    pub fn copy_within<R: RangeBounds<usize>>(&mut self, src: R, dest: usize) where T: Copy {
+        self.array.copy_within(src, dest);
+    }
+
+    /// Swap the elements of this array with those of `other` in bulk,
+    /// along with whether they are initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` doesn't have the length of this array.
+    pub fn swap_with_slice(&mut self, other: &mut BorrowingSlice<T>) {
+        assert_eq!(other.len(), self.len(), "other length does not match the array length");
+        unsafe { std::ptr::swap_nonoverlapping(self.array.as_mut_ptr(), other.array.as_mut_ptr(), self.len()); }
+        swap(&mut self.initialized, &mut other.initialized);
+    }
+}
+
+impl<T: Send + Clone> Clone for BorrowingSlice<T> {
+    fn clone(&self) -> Self {
+        let mut slice = self.rent_sibling();
+        if self.initialized {
+            let mut guard = unsafe { InitGuard::new(&mut slice) };
+            for x in self.iter() {
+                guard.push(x.clone());
+            }
+            guard.finish();
+            slice.initialized = true;
+        }
+        slice
+    }
+
+    /// Clone `source` into the array of this slice if it belongs to the
+    /// same size class of the same pool and has the same tag, instead of
+    /// renting a new one.
+    fn clone_from(&mut self, source: &Self) {
+        if !Arc::ptr_eq(&self.chain, &source.chain) || self.len() != source.len() || self.tag != source.tag {
+            *self = source.clone();
+            return;
+        }
+        match (self.initialized, source.initialized) {
+            (true, true) => self.array.clone_from_slice(&source.array),
+            (true, false) => {
+                // Leak rather than double-drop if an element panics
+                self.initialized = false;
+                unsafe { drop_in_place(self.array.get_ref_mut()); }
+            }
+            (false, true) => {
+                let mut guard = unsafe { InitGuard::new(&mut self.array) };
+                for x in source.iter() {
+                    guard.push(x.clone());
+                }
+                guard.finish();
+                self.initialized = true;
+            }
+            (false, false) => {}
         }
     }
 }
 
+#[cfg(feature = "bytemuck")]
+impl<T: Send + bytemuck::Pod> BorrowingSlice<T> {
+    /// View this array as a slice of `U`, sharing its storage.
+    ///
+    /// Fails if the size or alignment of the array doesn't fit `U`.
+    pub fn cast<U: bytemuck::Pod>(&self) -> Result<&[U], bytemuck::PodCastError> {
+        bytemuck::try_cast_slice(self.deref())
+    }
+
+    /// View this array as a mutable slice of `U`, sharing its storage.
+    ///
+    /// Fails if the size or alignment of the array doesn't fit `U`.
+    pub fn cast_mut<U: bytemuck::Pod>(&mut self) -> Result<&mut [U], bytemuck::PodCastError> {
+        bytemuck::try_cast_slice_mut(self.deref_mut())
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+impl BorrowingSlice<u8> {
+    /// View the bytes of this array as a slice of `R` records, as many as
+    /// fit whole, sharing its storage. The bytes past the last record are
+    /// left out.
+    ///
+    /// Fails if the array is not aligned for `R`, which never happens for
+    /// `Unaligned` records.
+    pub fn as_records<R: FromBytes + KnownLayout + Immutable>(&self) -> Result<&[R], CastError<&[u8], [R]>> {
+        let count = self.len() / size_of::<R>().max(1);
+        <[R]>::ref_from_prefix_with_elems(self.deref(), count).map(|(records, _)| records)
+    }
+
+    /// View the bytes of this array as a mutable slice of `R` records, see
+    /// `as_records`.
+    pub fn as_records_mut<R: FromBytes + IntoBytes + KnownLayout>(&mut self) -> Result<&mut [R], CastError<&mut [u8], [R]>> {
+        let count = self.len() / size_of::<R>().max(1);
+        <[R]>::mut_from_prefix_with_elems(self.deref_mut(), count).map(|(records, _)| records)
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum ArrayPoolError {
     MaxPowerTooSmall,
-    MaxChunkSizeNotSufficient
+    MaxChunkSizeNotSufficient,
+    InvalidAlignment,
+    AllocationForbidden,
+    RegionTooSmall,
+    Exhausted,
+    NoSizeClasses,
+    NoPools,
+    LockingUnsupported
+}
+
+impl From<ArrayPoolError> for io::Error {
+    fn from(error: ArrayPoolError) -> Self {
+        io::Error::new(io::ErrorKind::OutOfMemory, format!("could not rent a buffer: {error:?}"))
+    }
 }
 
+const PROFILE_HEADER: &str = "array_pool profile v1";
+
 impl<T: Send> ArrayPool<T>{
     /// Create a new `ArrayPool` with `max_power`.
     ///
     /// `max_power` determine how many size variants does a pool have.
     /// A `max_power` of x will create arrays with length 2^n (3 <= n < x).
     pub fn with_max_power(max_power: u8) -> Result<Self, ArrayPoolError> {
-        let mut map: BTreeMap<usize, Arc<BufferChain<T>>> = BTreeMap::new();
-        if max_power < 4 { return Err(ArrayPoolError::MaxPowerTooSmall); }
-        for x in 3..max_power {
-            map.insert(1usize << x, BufferChain::new(x));
-        }
-        Ok(Self {
-            empty_chain: BufferChain::new(0),
-            chunk_map: map
-        })
+        Self::with_sharding(max_power, Sharding::PerThread)
     }
 
-    /// Create a new `ArrayPool` with `max_power` of `target_pointer_width - 1`.
-    pub fn new() -> Self {
-        #[cfg(target_pointer_width = "64")]{
-            return Self::with_max_power(63).unwrap();
-        }
+    /// Create a new `ArrayPool` with `max_power`, splitting cached arrays
+    /// according to `sharding`.
+    pub fn with_sharding(max_power: u8, sharding: Sharding) -> Result<Self, ArrayPoolError> {
+        Self::from_config(PoolConfig { max_power, sharding, ..PoolConfig::default() })
+    }
+
+    /// Create a new `ArrayPool` from `config`.
+    pub fn from_config(config: PoolConfig) -> Result<Self, ArrayPoolError> {
+        Self::build(config, Self::config_allocator(&config)?)
+    }
 
-        #[cfg(target_pointer_width = "32")]{
-            return Self::with_max_power(31).unwrap();
+    /// Create a new `ArrayPool` from `config`, allocating its arrays with
+    /// `allocator` instead of the global allocator.
+    pub fn with_allocator(config: PoolConfig, allocator: Arc<dyn BufferAllocator>) -> Result<Self, ArrayPoolError> {
+        Self::build(config, Some(allocator))
+    }
+
+    /// Create a new `ArrayPool` from `config` whose arrays have one of the
+    /// lengths of `classes` instead of powers of two, `max_power` is ignored.
+    ///
+    /// Rents get an array of the smallest class that fits. Duplicate and
+    /// zero lengths are skipped.
+    pub fn with_size_classes(classes: &[usize], config: PoolConfig) -> Result<Self, ArrayPoolError> {
+        Self::build_classes(classes.iter().copied(), config, Self::config_allocator(&config)?)
+    }
+
+    /// Gets the allocator `config` asks for, if any.
+    #[cfg(feature = "mlock")]
+    fn config_allocator(config: &PoolConfig) -> Result<Option<Arc<dyn BufferAllocator>>, ArrayPoolError> {
+        Ok(config.lock_memory.then(|| Arc::new(crate::allocator::LockedAllocator) as Arc<dyn BufferAllocator>))
+    }
+
+    #[cfg(not(feature = "mlock"))]
+    fn config_allocator(config: &PoolConfig) -> Result<Option<Arc<dyn BufferAllocator>>, ArrayPoolError> {
+        if config.lock_memory { return Err(ArrayPoolError::LockingUnsupported); }
+        Ok(None)
+    }
+
+    /// Create a new `ArrayPool` from `config` carving `count` arrays for
+    /// each `(capacity, count)` pair of `classes` out of `region`, instead of
+    /// using an allocator. `max_power` and `fixed_per_class` are ignored.
+    ///
+    /// The pool never allocates: rents fail with `ArrayPoolError::Exhausted`
+    /// once every array is taken. Arrays freed by the limits of the pool
+    /// are lost until it is dropped. Fails if `region` can't hold every array.
+    pub fn with_backing(region: &'static mut [u8], classes: &[(usize, usize)], config: PoolConfig) -> Result<Self, ArrayPoolError> {
+        let config = PoolConfig { fixed_per_class: None, ..config };
+        let pool = Self::build_classes(classes.iter().map(|(capacity, _)| *capacity), config, None)?;
+        let mut address = region.as_mut_ptr() as usize;
+        let end = address + region.len();
+        let mut carved = vec![];
+        for (capacity, count) in classes.iter().filter(|(capacity, _)| *capacity > 0) {
+            let chain = pool.classes()[capacity].clone();
+            for _ in 0..*count {
+                let start = address.checked_next_multiple_of(chain.align);
+                let next = start.zip(capacity.checked_mul(size_of::<T>()))
+                    .and_then(|(start, bytes)| start.checked_add(bytes))
+                    .filter(|next| *next <= end)
+                    .ok_or(ArrayPoolError::RegionTooSmall)?;
+                carved.push((chain.clone(), next - chain.chunk_bytes()));
+                address = next;
+            }
         }
+        for (chain, start) in carved {
+            chain.cache_region(unsafe { RawBuffer::from_region(start as *mut T, chain.chunk_size) });
+        }
+        pool.freeze_allocations();
+        Ok(pool)
+    }
+
+    fn build(config: PoolConfig, allocator: Option<Arc<dyn BufferAllocator>>) -> Result<Self, ArrayPoolError> {
+        if config.max_power < 4 { return Err(ArrayPoolError::MaxPowerTooSmall); }
+        Self::build_classes((3..config.max_power).map(|x| 1usize << x), config, allocator)
     }
 
-    fn get_chain(&self, minimum_capacity: usize) -> Option<&Arc<BufferChain<T>>>{
-        for (size, chunk_chain) in &self.chunk_map {
-            if minimum_capacity <= *size {
-                return Some(chunk_chain);
+    fn build_classes<I: IntoIterator<Item = usize>>(classes: I, config: PoolConfig, allocator: Option<Arc<dyn BufferAllocator>>) -> Result<Self, ArrayPoolError> {
+        let mut map: BTreeMap<usize, Arc<BufferChain<T>>> = BTreeMap::new();
+        let align = config.alignment.unwrap_or(1).max(align_of::<T>());
+        if !align.is_power_of_two() { return Err(ArrayPoolError::InvalidAlignment); }
+        let limits = PoolLimits::new(&config);
+        let sharding = if config.deterministic { Sharding::PerThread } else { config.sharding };
+        for size in classes.into_iter().filter(|size| *size > 0) {
+            map.entry(size).or_insert_with(|| {
+                BufferChain::new(size, align, allocator.clone(), sharding, limits.clone())
+            });
+        }
+        if map.is_empty() { return Err(ArrayPoolError::NoSizeClasses); }
+        let pool = Self {
+            config,
+            resets: AtomicUsize::default(),
+            empty_chain: BufferChain::new(1, align, None, Sharding::PerThread, limits.clone()),
+            limits,
+            chunk_map: RwLock::new(map)
+        };
+        if let Some(count) = config.fixed_per_class {
+            for chain in pool.classes().values() {
+                chain.prewarm(count);
             }
+            pool.freeze_allocations();
         }
+        Ok(pool)
+    }
 
-        None
+    /// Create a new `ArrayPool` with `max_power` of `target_pointer_width - 1`.
+    pub fn new() -> Self {
+        Self::with_max_power((usize::BITS - 1) as u8).unwrap()
     }
 
-    /// Rent a new array with `minimum_capacity`.
-    ///
-    /// If no cached array was found, create a new one with each element
+    fn classes(&self) -> RwLockReadGuard<'_, BTreeMap<usize, Arc<BufferChain<T>>>> {
+        self.chunk_map.read().unwrap()
+    }
+
+    fn get_chain(&self, minimum_capacity: usize) -> Option<Arc<BufferChain<T>>>{
+        self.classes().range(minimum_capacity..).next().map(|(_, chain)| chain.clone())
+    }
+
+    /// Rent a new array with `minimum_capacity`, each element is
     /// initialized by `fabricator`.
     pub fn rent_with<F: FnMut() -> T>(&self, minimum_capacity: usize, fabricator: &mut F) -> Result<BorrowingSlice<T>, ArrayPoolError> {
         if let Some(chunk_chain) = self.get_chain(minimum_capacity){
-            return Ok(chunk_chain.rent_with(fabricator));
+            return chunk_chain.rent_with(fabricator);
         }
 
         Err(ArrayPoolError::MaxChunkSizeNotSufficient)
     }
 
+    /// Rent a new array with `minimum_capacity`, each element is a clone of
+    /// `value`.
+    pub fn rent_filled(&self, minimum_capacity: usize, value: T) -> Result<BorrowingSlice<T>, ArrayPoolError> where T: Clone {
+        let mut slice = unsafe { self.rent_or_create_uninitialized(minimum_capacity, false) }?;
+        // Cached arrays had their elements dropped on return, avoid dropping them again
+        unsafe { fill_uninitialized(&mut slice, value); }
+        slice.initialized = true;
+        Ok(slice)
+    }
+
     /// Rent a new array with `minimum_capacity`.
     ///
     /// If no cached array was found, create a new one without initializing it,
     /// zero if needed.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned array may be uninitialized and will not be
+    /// dropped when the array is returned.
     pub unsafe fn rent_or_create_uninitialized(&self, minimum_capacity: usize, zeroed: bool) -> Result<BorrowingSlice<T>, ArrayPoolError> {
         if let Some(chunk_chain) = self.get_chain(minimum_capacity){
-            return Ok(chunk_chain.rent_or_create_uninitialized(zeroed));
+            return chunk_chain.rent_or_create_uninitialized(zeroed);
         }
 
         Err(ArrayPoolError::MaxChunkSizeNotSufficient)
     }
 
-    /// Rent an array with the smallest supported capacity.
-    ///
-    /// If no cached array was found, create a new one with each element
+    /// Rent an array with the smallest supported capacity, each element is
     /// initialized by `fabricator`.
     pub fn rent_minimum_with<F: FnMut() -> T>(&self, fabricator: &mut F) -> Result<BorrowingSlice<T>, ArrayPoolError>{
-        for (_, chunk_chain) in &self.chunk_map {
-            return Ok(chunk_chain.rent_with(fabricator));
+        let chain = self.classes().values().next().cloned();
+        match chain {
+            Some(chunk_chain) => chunk_chain.rent_with(fabricator),
+            None => Err(ArrayPoolError::MaxChunkSizeNotSufficient)
         }
-
-        Err(ArrayPoolError::MaxChunkSizeNotSufficient)
     }
 
     /// Rent an array with the smallest supported capacity.
     ///
     /// If no cached array was found, create a new one without initializing it,
     /// zero if needed.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned array may be uninitialized and will not be
+    /// dropped when the array is returned.
     pub unsafe fn rent_or_create_minimum_uninitialized(&self, zeroed: bool) -> Result<BorrowingSlice<T>, ArrayPoolError> {
-        for (_, chunk_chain) in &self.chunk_map {
-            return Ok(chunk_chain.rent_or_create_uninitialized(zeroed));
+        let chain = self.classes().values().next().cloned();
+        match chain {
+            Some(chunk_chain) => chunk_chain.rent_or_create_uninitialized(zeroed),
+            None => Err(ArrayPoolError::MaxChunkSizeNotSufficient)
         }
-
-        Err(ArrayPoolError::MaxChunkSizeNotSufficient)
     }
 
     /// Double the capacity of `old_buffer`. New slots won't be initialized.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is treated as uninitialized, its elements
+    /// will not be dropped when it is returned.
     pub unsafe fn expand_buffer(&self, mut old_buffer: BorrowingSlice<T>) -> Result<BorrowingSlice<T>, ArrayPoolError> {
         let old_size = old_buffer.len();
         let new_size = old_size * 2;
-        if let Ok(mut new_buffer) = unsafe {self.rent_or_create_uninitialized(new_size, false)} {
-            for i in 0..old_size {
-                swap(&mut old_buffer[i], &mut new_buffer[i]);
-            }
+        let mut new_buffer = unsafe {self.rent_or_create_uninitialized(new_size, false)}?;
+        if let Some(tag) = old_buffer.tag {
+            new_buffer.set_tag(tag);
+        }
+        // A bitwise move can't panic, leaving no half-moved state behind
+        std::ptr::copy_nonoverlapping(old_buffer.as_ptr(), new_buffer.as_mut_ptr(), old_size);
 
-            old_buffer.initialized = false;
-            drop(old_buffer);
-            Ok(new_buffer)
-        } else { Err(ArrayPoolError::MaxChunkSizeNotSufficient) }
+        old_buffer.initialized = false;
+        drop(old_buffer);
+        Ok(new_buffer)
     }
 
-    /// Halve the capacity of `old_buffer`. Old slots won't be dropped.
+    /// Halve the capacity of `old_buffer`, or move it into the smallest
+    /// class holding half of it. Old slots won't be dropped.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is treated as uninitialized, its elements
+    /// will not be dropped when it is returned.
     pub unsafe fn shrink_buffer(&self, mut old_buffer: BorrowingSlice<T>) -> BorrowingSlice<T> {
         let old_size = old_buffer.len();
         let new_size = old_size / 2;
+        let smaller = self.get_chain(new_size).is_some_and(|chain| chain.chunk_size < old_size);
 
-        if let Ok(mut new_buffer) = unsafe {self.rent_or_create_uninitialized(new_size, false)} {
+        if !smaller {
+            old_buffer
+        } else if let Ok(mut new_buffer) = unsafe {self.rent_or_create_uninitialized(new_size, false)} {
+            if let Some(tag) = old_buffer.tag {
+                new_buffer.set_tag(tag);
+            }
             for i in 0..new_size {
                 swap(&mut old_buffer[i], &mut new_buffer[i]);
             }
@@ -374,32 +1477,386 @@ impl<T: Send> ArrayPool<T>{
             array: RawBuffer::empty(),
             chain: self.empty_chain.clone(),
             initialized: true,
+            tag: None,
+            #[cfg(feature = "backtrace")]
+            rental: 0,
         }
     }
 
     /// Gets the smallest supported capacity.
     pub fn min_size(&self) -> usize {
-        *self.chunk_map.first_key_value().unwrap().0
+        *self.classes().first_key_value().unwrap().0
     }
 
     /// Gets the largest supported capacity.
     pub fn max_size(&self) -> usize {
-        *self.chunk_map.last_key_value().unwrap().0
+        *self.classes().last_key_value().unwrap().0
+    }
+
+    /// Add a size class of arrays with `capacity` elements, returns false
+    /// if the pool already has one or `capacity` is zero.
+    ///
+    /// Rents that fit go to the new class from now on. Pools created with
+    /// `PoolConfig::fixed_per_class` prewarm it as many arrays. Like other
+    /// custom classes it is kept by `clone_empty` but not by `config`.
+    pub fn add_size_class(&self, capacity: usize) -> bool {
+        if capacity == 0 { return false; }
+        let mut classes = self.chunk_map.write().unwrap();
+        if classes.contains_key(&capacity) { return false; }
+        let template = classes.values().next().unwrap();
+        let sharding = if self.config.deterministic { Sharding::PerThread } else { self.config.sharding };
+        let chain = BufferChain::new(capacity, template.align, template.allocator.clone(), sharding, self.limits.clone());
+        if let Some(count) = self.config.fixed_per_class {
+            chain.prewarm(count);
+        }
+        classes.insert(capacity, chain);
+        true
+    }
+
+    /// Remove the size class of arrays with `capacity` elements, freeing
+    /// the arrays it caches. Returns false if the pool has no such class.
+    ///
+    /// Later rents go to the next larger class, arrays of the retired class
+    /// still rented are freed when returned. Fails if it is the last class.
+    pub fn retire_size_class(&self, capacity: usize) -> Result<bool, ArrayPoolError> {
+        let mut classes = self.chunk_map.write().unwrap();
+        if !classes.contains_key(&capacity) { return Ok(false); }
+        if classes.len() == 1 { return Err(ArrayPoolError::NoSizeClasses); }
+        let chain = classes.remove(&capacity).unwrap();
+        drop(classes);
+        chain.retired.store(true, Ordering::Release);
+        chain.evict_while(|| true);
+        Ok(true)
+    }
+
+    /// Free arrays of at least `bytes` when they are returned instead of
+    /// caching them, or cache every size class if `None`.
+    ///
+    /// Cached arrays above the new threshold are freed right away.
+    pub fn set_discard_threshold(&self, bytes: Option<usize>) {
+        let threshold = bytes.unwrap_or(usize::MAX);
+        self.limits.discard_above.store(threshold, Ordering::Relaxed);
+        for chain in self.classes().values().filter(|chain| chain.chunk_bytes() >= threshold) {
+            chain.evict_while(|| true);
+        }
+    }
+
+    /// Limit the total size in bytes of the cached arrays, or lift the limit
+    /// if `None`. Arrays returned past this budget are freed.
+    ///
+    /// If the pool caches more than the new budget, arrays are freed right
+    /// away, largest size class first.
+    pub fn set_max_cached_bytes(&self, bytes: Option<usize>) {
+        let max = bytes.unwrap_or(usize::MAX);
+        self.limits.max_cached_bytes.store(max, Ordering::Relaxed);
+        for chain in self.classes().values().rev() {
+            chain.evict_while(|| self.limits.cached_bytes.load(Ordering::Acquire) > max);
+        }
+    }
+
+    /// Start recording the latency of rents, returns, steals and
+    /// allocations, keeping the last `capacity` events.
+    ///
+    /// Restarting drops the events recorded so far.
+    #[cfg(feature = "profiler")]
+    pub fn start_profiling(&self, capacity: usize) {
+        self.limits.events.enable(capacity);
+    }
+
+    /// Stop recording events, the recorded ones can still be drained.
+    #[cfg(feature = "profiler")]
+    pub fn stop_profiling(&self) {
+        self.limits.events.disable();
+    }
+
+    /// Take the recorded events, oldest first.
+    #[cfg(feature = "profiler")]
+    pub fn drain_events(&self) -> Vec<PoolEvent> {
+        self.limits.events.drain()
+    }
+
+    /// Gets a copy of the recorded events, oldest first, leaving them in
+    /// place, e.g. to dump the last operations when something goes wrong.
+    #[cfg(feature = "profiler")]
+    pub fn recent_events(&self) -> Vec<PoolEvent> {
+        self.limits.events.recent()
+    }
+
+    /// Gets the number of events not recorded because another thread was
+    /// recording at the same time.
+    #[cfg(feature = "profiler")]
+    pub fn dropped_events(&self) -> usize {
+        self.limits.events.dropped()
+    }
+
+    /// Start counting rents per call stack, captured with a backtrace on
+    /// every rent. See `take_rent_sites`.
+    #[cfg(feature = "profiler")]
+    pub fn start_site_profiling(&self) {
+        self.limits.sites.set_enabled(true);
+    }
+
+    /// Stop counting rents per call stack.
+    #[cfg(feature = "profiler")]
+    pub fn stop_site_profiling(&self) {
+        self.limits.sites.set_enabled(false);
+    }
+
+    /// Take the rents counted per call stack, most renting first.
+    ///
+    /// Export them for a flamegraph with `profiler::write_folded`.
+    #[cfg(feature = "profiler")]
+    pub fn take_rent_sites(&self) -> Vec<RentSite> {
+        self.limits.sites.take()
+    }
+
+    /// Limit the number of arrays each thread caches per size class, or
+    /// lift the limit if `None`. Arrays returned past this cap go to a chain
+    /// shared by every thread.
+    pub fn set_max_cached_per_thread(&self, count: Option<usize>) {
+        self.limits.max_cached_per_thread.store(count.unwrap_or(usize::MAX), Ordering::Relaxed);
+    }
+
+    /// Let a thread whose chain is empty allocate for `misses` consecutive
+    /// rents before it looks for an array in other threads' chains.
+    pub fn set_steal_threshold(&self, misses: usize) {
+        self.limits.steal_after_misses.store(misses, Ordering::Relaxed);
+    }
+
+    /// Stop this pool from ever allocating again, typically after warming
+    /// it up. Rents that can't be served by a cached array fail with
+    /// `ArrayPoolError::Exhausted`.
+    pub fn freeze_allocations(&self) {
+        self.limits.frozen.store(true, Ordering::Relaxed);
+    }
+
+    /// Forbid this pool from allocating a new array on the current thread.
+    ///
+    /// Rents on this thread can still be served by cached arrays. A rent
+    /// that would allocate panics on debug builds and fails with
+    /// `ArrayPoolError::AllocationForbidden` otherwise.
+    pub fn forbid_alloc_on_current_thread(&self) {
+        self.limits.set_alloc_forbidden(true);
+    }
+
+    /// Allow this pool to allocate new arrays on the current thread again.
+    pub fn allow_alloc_on_current_thread(&self) {
+        self.limits.set_alloc_forbidden(false);
+    }
+
+    /// Enable or disable zeroing arrays when they are returned.
+    pub fn set_scrub(&self, enabled: bool) {
+        self.limits.scrub.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Move the arrays cached by `other` into this pool, for size classes
+    /// both pools have, and return how many were moved. Arrays rented from
+    /// `other` are not affected.
+    ///
+    /// Arrays past the cache limits of this pool stay in `other`. Fails if
+    /// the arrays of both pools don't have the same alignment.
+    pub fn absorb(&self, other: &ArrayPool<T>) -> Result<usize, ArrayPoolError> {
+        if self.empty_chain.align != other.empty_chain.align {
+            return Err(ArrayPoolError::InvalidAlignment);
+        }
+        if std::ptr::eq(self, other) { return Ok(0); }
+        Ok(self.classes().iter()
+            .filter_map(|(size, chain)| Some(chain.absorb(other.classes().get(size)?)))
+            .sum())
+    }
+
+    /// Rebalance the cache to the demand since the last compaction: size
+    /// classes that missed get as many arrays allocated ahead, the others
+    /// free the cached arrays past the number of rents they served.
+    ///
+    /// Meant for quiescent periods, new arrays stay within the cache limits.
+    /// Frozen pools are left as is, since freed arrays could never be
+    /// allocated again, and nothing is allocated on threads forbidden to.
+    pub fn compact(&self) -> Compaction {
+        if self.limits.frozen.load(Ordering::Relaxed) { return Compaction::default(); }
+        self.classes().values()
+            .fold(Compaction::default(), |acc, chain| acc + chain.compact())
+    }
+
+    /// Gets the settings of this pool, including the limits changed since
+    /// its creation, so `ArrayPool::from_config` can create an empty pool
+    /// behaving the same way.
+    ///
+    /// Custom size classes and a custom allocator are not part of the
+    /// settings, see `clone_empty` to keep them.
+    pub fn config(&self) -> PoolConfig {
+        let limit = |bytes: usize| (bytes != usize::MAX).then_some(bytes);
+        PoolConfig {
+            discard_above: limit(self.limits.discard_above.load(Ordering::Relaxed)),
+            max_cached_bytes: limit(self.limits.max_cached_bytes.load(Ordering::Relaxed)),
+            max_cached_per_thread: limit(self.limits.max_cached_per_thread.load(Ordering::Relaxed)),
+            steal_after_misses: self.limits.steal_after_misses.load(Ordering::Relaxed),
+            scrub: self.limits.scrub.load(Ordering::Relaxed),
+            ..self.config
+        }
+    }
+
+    /// Gets the capacities of the size classes of this pool, in ascending order.
+    pub fn size_classes(&self) -> Vec<usize> {
+        self.classes().keys().copied().collect()
+    }
+
+    /// Create a new, empty pool with the settings, size classes and
+    /// allocator of this pool.
+    pub fn clone_empty(&self) -> Result<Self, ArrayPoolError> {
+        let allocator = self.classes().values().next().unwrap().allocator.clone();
+        Self::build_classes(self.size_classes(), self.config(), allocator)
+    }
+
+    /// Gets the usage counters of this pool, summed over every size class.
+    pub fn stats(&self) -> PoolStats {
+        self.classes().values()
+            .fold(PoolStats::default(), |acc, chain| acc + chain.stats())
+    }
+
+    /// Gets the usage counters of this pool along with the current instant,
+    /// for computing per-interval activity with `StatsSnapshot::delta`.
+    pub fn stats_snapshot(&self) -> StatsSnapshot {
+        let resets = self.resets.load(Ordering::Acquire);
+        StatsSnapshot { taken_at: Instant::now(), resets, stats: self.stats() }
+    }
+
+    /// Zero the usage counters of this pool. `cached` and `cached_bytes`
+    /// keep tracking the cached arrays.
+    ///
+    /// Arrays rented before the reset and returned after it make `returns`
+    /// exceed `rents`.
+    pub fn reset_stats(&self) {
+        for chain in self.classes().values() {
+            chain.reset_stats();
+        }
+        for stats in self.limits.tags.lock().unwrap().values_mut() {
+            stats.rents = 0;
+        }
+        for counters in self.limits.threads.iter() {
+            counters.reset();
+        }
+        self.resets.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Gets every array currently rented from this pool, largest first,
+    /// with the backtraces of its rent and allocation.
+    #[cfg(feature = "backtrace")]
+    pub fn outstanding_rentals(&self) -> Vec<Rental> {
+        self.limits.rentals.outstanding()
+    }
+
+    /// Gets the rent counters of every thread that rented from this pool.
+    ///
+    /// Threads that exited are kept until a new thread takes their place.
+    pub fn thread_stats(&self) -> Vec<ThreadStats> {
+        self.limits.threads.iter().map(|counters| counters.stats()).collect()
+    }
+
+    /// Gets the usage of the arrays rented under each tag, see `rent_tagged`.
+    pub fn tag_stats(&self) -> BTreeMap<&'static str, TagStats> {
+        self.limits.tags.lock().unwrap().iter()
+            .map(|(tag, stats)| (*tag, *stats))
+            .collect()
+    }
+
+    /// Gets the usage counters of each size class, keyed by its capacity.
+    pub fn class_stats(&self) -> Vec<(usize, PoolStats)> {
+        self.classes().iter()
+            .map(|(size, chain)| (*size, chain.stats()))
+            .collect()
+    }
+
+    /// Write the most arrays of each size class rented at once since the
+    /// pool was created or its counters reset, so a fresh pool can be
+    /// warmed up with `prewarm_from_profile`.
+    ///
+    /// The profile is a text file with one `<capacity> <count>` line per
+    /// size class in use.
+    pub fn save_profile<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{PROFILE_HEADER}")?;
+        for (size, chain) in self.classes().iter() {
+            let peak = chain.peak_rented.load(Ordering::Relaxed);
+            if peak > 0 {
+                writeln!(writer, "{size} {peak}")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Allocate and cache the arrays listed in a profile written by
+    /// `save_profile`.
+    ///
+    /// Capacities this pool doesn't support are skipped, nothing is
+    /// allocated once allocations are frozen or forbidden on the current
+    /// thread.
+    pub fn prewarm_from_profile<R: Read>(&self, reader: R) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut lines = BufReader::new(reader).lines();
+        if lines.next().transpose()?.as_deref() != Some(PROFILE_HEADER) {
+            return Err(invalid("missing pool profile header"));
+        }
+        for line in lines {
+            let line = line?;
+            let mut fields = line.split_whitespace().map(str::parse::<usize>);
+            let (Some(Ok(size)), Some(Ok(count)), None) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(invalid("malformed pool profile line"));
+            };
+            if self.limits.frozen.load(Ordering::Relaxed) || self.limits.alloc_forbidden() { continue; }
+            if let Some(chain) = self.classes().get(&size) {
+                chain.prewarm(count);
+            }
+        }
+        Ok(())
+    }
+}
+
+struct UsedClasses<'a, T: Send>(&'a BTreeMap<usize, Arc<BufferChain<T>>>);
+
+impl<T: Send> Debug for UsedClasses<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().filter(|(_, chain)| chain.rents.load(Ordering::Relaxed) > 0))
+            .finish()
+    }
+}
+
+impl<T: Send> Debug for ArrayPool<T> {
+    /// Only the size classes that have been rented from are listed.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let classes = self.classes();
+        f.debug_struct("ArrayPool")
+            .field("min_size", classes.first_key_value().unwrap().0)
+            .field("max_size", classes.last_key_value().unwrap().0)
+            .field("size_classes", &classes.len())
+            .field("sharding", &self.config.sharding)
+            .field("classes", &UsedClasses(&classes))
+            .finish()
+    }
+}
+
+impl<T: Send> Default for ArrayPool<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl<T: Default + Send> ArrayPool<T>{
-    /// Rent a new array with `minimum_capacity`.
-    ///
-    /// If no cached array was found, create a new one with each element
+    /// Rent a new array with `minimum_capacity`, each element is
     /// initialized by the default constructor.
     pub fn rent(&self, minimum_capacity: usize) -> Result<BorrowingSlice<T>, ArrayPoolError> {
         self.rent_with(minimum_capacity, &mut T::default)
     }
 
-    /// Rent an array with the smallest supported capacity.
-    ///
-    /// If no cached array was found, create a new one with each element
+    /// Rent a new array like `rent`, attributed to `tag` in `tag_stats`
+    /// until it is returned.
+    pub fn rent_tagged(&self, minimum_capacity: usize, tag: &'static str) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        let mut slice = self.rent(minimum_capacity)?;
+        slice.set_tag(tag);
+        Ok(slice)
+    }
+
+    /// Rent an array with the smallest supported capacity, each element is
     /// initialized by the default constructor.
     pub fn rent_minimum(&self) -> Result<BorrowingSlice<T>, ArrayPoolError>{
         self.rent_minimum_with(&mut T::default)
@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backend::PoolBackend;
+use crate::config::PoolConfig;
+use crate::pool::{ArrayPool, ArrayPoolError, BorrowingSlice};
+use crate::stats::PoolStats;
+
+/// Picks which pool of a `PoolSet` serves a rent.
+pub trait Routing<T: Send>: Send + Sync {
+    /// Gets the index in `pools` of the pool renting an array with
+    /// `minimum_capacity`. `pools` is never empty.
+    fn route(&self, pools: &[ArrayPool<T>], minimum_capacity: usize) -> usize;
+}
+
+/// Hands rents to every pool in turn.
+#[derive(Debug, Default)]
+pub struct RoundRobin(AtomicUsize);
+
+/// Hands rents to the pool with the fewest arrays currently rented.
+///
+/// Sums the counters of every size class of every pool on each rent.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LeastLoaded;
+
+/// Hands rents to the pool picked by a key, such as the NUMA node or the
+/// tenant of the calling thread, modulo the number of pools.
+pub struct Affinity<F>(pub F);
+
+impl<T: Send> Routing<T> for RoundRobin {
+    fn route(&self, pools: &[ArrayPool<T>], _minimum_capacity: usize) -> usize {
+        self.0.fetch_add(1, Ordering::Relaxed) % pools.len()
+    }
+}
+
+impl<T: Send> Routing<T> for LeastLoaded {
+    fn route(&self, pools: &[ArrayPool<T>], _minimum_capacity: usize) -> usize {
+        pools.iter()
+            .map(|pool| pool.stats())
+            .enumerate()
+            .min_by_key(|(_, stats)| stats.rents.saturating_sub(stats.returns))
+            .map_or(0, |(index, _)| index)
+    }
+}
+
+impl<T: Send, F: Fn() -> usize + Send + Sync> Routing<T> for Affinity<F> {
+    fn route(&self, pools: &[ArrayPool<T>], _minimum_capacity: usize) -> usize {
+        (self.0)() % pools.len()
+    }
+}
+
+/// Owns several `ArrayPool`s, e.g. one per NUMA node or per tenant, and
+/// routes each rent to one of them according to `R`.
+///
+/// Arrays go back to the pool that rented them when dropped.
+pub struct PoolSet<T: Send, R = RoundRobin> {
+    pools: Vec<ArrayPool<T>>,
+    routing: R,
+}
+
+impl<T: Send, R: Routing<T>> PoolSet<T, R> {
+    /// Create a new `PoolSet` routing rents across `pools`.
+    pub fn with_pools(pools: Vec<ArrayPool<T>>, routing: R) -> Result<Self, ArrayPoolError> {
+        if pools.is_empty() { return Err(ArrayPoolError::NoPools); }
+        Ok(Self { pools, routing })
+    }
+
+    /// Create a new `PoolSet` of `count` pools created from `config`.
+    pub fn from_config(count: usize, config: PoolConfig, routing: R) -> Result<Self, ArrayPoolError> {
+        let pools = (0..count)
+            .map(|_| ArrayPool::from_config(config))
+            .collect::<Result<_, _>>()?;
+        Self::with_pools(pools, routing)
+    }
+
+    /// Gets the pools of this set.
+    pub fn pools(&self) -> &[ArrayPool<T>] {
+        &self.pools
+    }
+
+    /// Gets the pool serving the next rent of `minimum_capacity`.
+    pub fn route(&self, minimum_capacity: usize) -> &ArrayPool<T> {
+        let index = self.routing.route(&self.pools, minimum_capacity);
+        &self.pools[index]
+    }
+
+    /// Rent a new array with `minimum_capacity` from the routed pool, each
+    /// element is initialized by `fabricator`.
+    pub fn rent_with<F: FnMut() -> T>(&self, minimum_capacity: usize, fabricator: &mut F) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        self.route(minimum_capacity).rent_with(minimum_capacity, fabricator)
+    }
+
+    /// Rent a new array with `minimum_capacity` from the routed pool, each
+    /// element is a clone of `value`.
+    pub fn rent_filled(&self, minimum_capacity: usize, value: T) -> Result<BorrowingSlice<T>, ArrayPoolError> where T: Clone {
+        self.route(minimum_capacity).rent_filled(minimum_capacity, value)
+    }
+
+    /// Rent a new array with `minimum_capacity` from the routed pool.
+    ///
+    /// # Safety
+    ///
+    /// The content of the returned array may be uninitialized and will not be
+    /// dropped when the array is returned.
+    pub unsafe fn rent_or_create_uninitialized(&self, minimum_capacity: usize, zeroed: bool) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        self.route(minimum_capacity).rent_or_create_uninitialized(minimum_capacity, zeroed)
+    }
+
+    /// Rent an array with the smallest supported capacity from the routed
+    /// pool, each element is initialized by `fabricator`.
+    pub fn rent_minimum_with<F: FnMut() -> T>(&self, fabricator: &mut F) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        self.route(self.min_size()).rent_minimum_with(fabricator)
+    }
+
+    /// Rent an empty array.
+    pub fn rent_empty(&self) -> BorrowingSlice<T> {
+        self.pools[0].rent_empty()
+    }
+
+    /// Gets the smallest capacity supported by every pool.
+    pub fn min_size(&self) -> usize {
+        self.pools.iter().map(ArrayPool::min_size).max().unwrap()
+    }
+
+    /// Gets the largest capacity supported by every pool.
+    pub fn max_size(&self) -> usize {
+        self.pools.iter().map(ArrayPool::max_size).min().unwrap()
+    }
+
+    /// Gets the usage counters of every pool, summed together.
+    pub fn stats(&self) -> PoolStats {
+        self.pools.iter()
+            .fold(PoolStats::default(), |acc, pool| acc + pool.stats())
+    }
+}
+
+impl<T: Default + Send, R: Routing<T>> PoolSet<T, R> {
+    /// Rent a new array with `minimum_capacity` from the routed pool, each
+    /// element is initialized by the default constructor.
+    pub fn rent(&self, minimum_capacity: usize) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        self.rent_with(minimum_capacity, &mut T::default)
+    }
+
+    /// Rent an array with the smallest supported capacity from the routed
+    /// pool, each element is initialized by the default constructor.
+    pub fn rent_minimum(&self) -> Result<BorrowingSlice<T>, ArrayPoolError> {
+        self.rent_minimum_with(&mut T::default)
+    }
+}
+
+impl<T: Send, R: Routing<T>> PoolBackend<T> for PoolSet<T, R> {
+    type Buffer = BorrowingSlice<T>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.route(minimum_capacity).rent_uninitialized(minimum_capacity)
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.route(self.min_size()).rent_minimum_uninitialized()
+    }
+
+    fn return_buffer(&self, buffer: Self::Buffer) {
+        // Every array knows the pool it belongs to
+        self.pools[0].return_buffer(buffer);
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        self.route(buffer.len() * 2).expand_buffer(buffer)
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        self.route(buffer.len() / 2).shrink_buffer(buffer)
+    }
+
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        buffer.set_tag(tag);
+    }
+
+    fn min_size(&self) -> usize {
+        PoolSet::min_size(self)
+    }
+
+    fn max_size(&self) -> usize {
+        PoolSet::max_size(self)
+    }
+}
@@ -0,0 +1,235 @@
+use std::io::{self, Write};
+use std::thread::ThreadId;
+use std::time::Duration;
+#[cfg(feature = "profiler")]
+use std::backtrace::Backtrace;
+#[cfg(feature = "profiler")]
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "profiler")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "profiler")]
+use std::sync::{Mutex, TryLockError};
+#[cfg(feature = "profiler")]
+use std::time::Instant;
+
+/// What a recorded `PoolEvent` measured.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PoolEventKind {
+    /// A whole rent, including any steal or allocation it needed.
+    Rent,
+    /// A whole return, including dropping the elements of the array.
+    Return,
+    /// A look for an array in other threads' chains, successful or not.
+    Steal,
+    /// The allocation of a new array.
+    Allocation,
+    /// The release of a cached array after lowering the pool's limits or
+    /// by a compaction.
+    Eviction,
+}
+
+/// An operation of a pool recorded while profiling, see
+/// `ArrayPool::start_profiling`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PoolEvent {
+    pub kind: PoolEventKind,
+    /// Capacity of the size class involved.
+    pub capacity: usize,
+    /// When the operation started, since profiling started.
+    pub at: Duration,
+    /// How long the operation took.
+    pub duration: Duration,
+    /// Thread that ran the operation.
+    pub thread: ThreadId,
+}
+
+/// Rents made from one call stack, see `ArrayPool::take_rent_sites`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RentSite {
+    /// Frames of the call stack, outermost first.
+    pub stack: Vec<String>,
+    /// Number of rents made from this stack.
+    pub rents: usize,
+    /// Number of these rents that had to allocate a new array.
+    pub allocations: usize,
+    /// Total size in bytes of the allocated arrays.
+    pub allocated_bytes: usize,
+}
+
+/// Which counter of a `RentSite` to export with `write_folded`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SiteMetric {
+    Rents,
+    Allocations,
+    AllocatedBytes,
+}
+
+impl RentSite {
+    /// Gets the value of `metric` for this site.
+    pub fn count(&self, metric: SiteMetric) -> usize {
+        match metric {
+            SiteMetric::Rents => self.rents,
+            SiteMetric::Allocations => self.allocations,
+            SiteMetric::AllocatedBytes => self.allocated_bytes,
+        }
+    }
+}
+
+/// Write `sites` in the folded stack format read by `flamegraph.pl`,
+/// `inferno` and `pprof`, one `frame;frame;frame count` line per site.
+///
+/// Sites whose `metric` is zero are skipped.
+pub fn write_folded<W: Write>(sites: &[RentSite], metric: SiteMetric, mut writer: W) -> io::Result<()> {
+    for site in sites {
+        let count = site.count(metric);
+        if count == 0 { continue; }
+        let frames: Vec<String> = site.stack.iter().map(|frame| frame.replace(';', ",")).collect();
+        writeln!(writer, "{} {count}", frames.join(";"))?;
+    }
+    Ok(())
+}
+
+/// Frames of the current call stack, outermost first, without the frames
+/// of the pool itself nor those of the runtime starting the thread.
+#[cfg(feature = "profiler")]
+fn capture_stack() -> Vec<String> {
+    let backtrace = Backtrace::force_capture().to_string();
+    let mut frames: Vec<String> = backtrace.lines()
+        .filter_map(|line| {
+            let (index, symbol) = line.trim_start().split_once(": ")?;
+            index.parse::<usize>().ok().map(|_| symbol.to_string())
+        })
+        .skip_while(|symbol| {
+            ["array_pool::pool::", "array_pool::profiler::", "<array_pool::pool::"]
+                .iter().any(|prefix| symbol.starts_with(prefix))
+        })
+        .take_while(|symbol| !symbol.contains("__rust_begin_short_backtrace"))
+        .collect();
+    frames.reverse();
+    frames
+}
+
+/// Counts rents per call stack while enabled.
+#[cfg(feature = "profiler")]
+pub(crate) struct SiteProfile {
+    enabled: AtomicBool,
+    sites: Mutex<HashMap<Vec<String>, RentSite>>,
+}
+
+#[cfg(feature = "profiler")]
+impl SiteProfile {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            sites: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Count a rent from the current call stack, which allocated
+    /// `allocated_bytes` if not zero.
+    pub fn record(&self, allocated_bytes: usize) {
+        if !self.enabled.load(Ordering::Relaxed) { return; }
+        let stack = capture_stack();
+        let mut sites = self.sites.lock().unwrap();
+        let site = sites.entry(stack).or_insert_with_key(|stack| RentSite { stack: stack.clone(), ..RentSite::default() });
+        site.rents += 1;
+        if allocated_bytes > 0 {
+            site.allocations += 1;
+            site.allocated_bytes += allocated_bytes;
+        }
+    }
+
+    pub fn take(&self) -> Vec<RentSite> {
+        let mut sites: Vec<RentSite> = self.sites.lock().unwrap().drain().map(|(_, site)| site).collect();
+        sites.sort_by(|a, b| b.rents.cmp(&a.rents).then_with(|| a.stack.cmp(&b.stack)));
+        sites
+    }
+}
+
+/// Holds the most recent events of a pool.
+///
+/// Recording never waits: an event is dropped if another thread is
+/// recording at the same time.
+#[cfg(feature = "profiler")]
+pub(crate) struct EventRing {
+    enabled: AtomicBool,
+    dropped: AtomicUsize,
+    ring: Mutex<Ring>,
+}
+
+#[cfg(feature = "profiler")]
+struct Ring {
+    started: Instant,
+    capacity: usize,
+    events: VecDeque<PoolEvent>,
+}
+
+#[cfg(feature = "profiler")]
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Self { started: Instant::now(), capacity, events: VecDeque::with_capacity(capacity) }
+    }
+}
+
+#[cfg(feature = "profiler")]
+impl EventRing {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            dropped: AtomicUsize::default(),
+            ring: Mutex::new(Ring::new(0)),
+        }
+    }
+
+    /// Gets the start of an operation if profiling.
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.load(Ordering::Relaxed).then(Instant::now)
+    }
+
+    /// Record an operation on arrays of `capacity` that began at `started`.
+    pub fn record(&self, kind: PoolEventKind, capacity: usize, started: Option<Instant>) {
+        let Some(started) = started else { return };
+        let duration = started.elapsed();
+        let mut ring = match self.ring.try_lock() {
+            Ok(ring) => ring,
+            Err(TryLockError::WouldBlock) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Err(TryLockError::Poisoned(poisoned)) => panic!("{poisoned}"),
+        };
+        if ring.capacity == 0 { return; }
+        if ring.events.len() == ring.capacity {
+            ring.events.pop_front();
+        }
+        let at = started.saturating_duration_since(ring.started);
+        let thread = std::thread::current().id();
+        ring.events.push_back(PoolEvent { kind, capacity, at, duration, thread });
+    }
+
+    pub fn enable(&self, capacity: usize) {
+        *self.ring.lock().unwrap() = Ring::new(capacity);
+        self.dropped.store(0, Ordering::Relaxed);
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+    }
+
+    pub fn drain(&self) -> Vec<PoolEvent> {
+        self.ring.lock().unwrap().events.drain(..).collect()
+    }
+
+    pub fn recent(&self) -> Vec<PoolEvent> {
+        self.ring.lock().unwrap().events.iter().copied().collect()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -1,13 +1,25 @@
-use std::alloc::{alloc, alloc_zeroed, dealloc, Layout};
+use std::alloc::{alloc, alloc_zeroed, dealloc, handle_alloc_error, Layout};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::ptr::{slice_from_raw_parts, slice_from_raw_parts_mut};
+use std::sync::Arc;
+
+use crate::allocator::BufferAllocator;
+
+/// Where the memory of a `RawBuffer` comes from.
+enum Source {
+    /// Not owned, never freed.
+    Borrowed,
+    Global,
+    Custom(Arc<dyn BufferAllocator>),
+}
 
 pub struct RawBuffer<T>{
     phantom_of_the_opera: PhantomData<T>,
     capacity: usize,
     layout: Layout,
-    pointer: usize
+    pointer: usize,
+    source: Source
 }
 
 impl<T> RawBuffer<T>{
@@ -17,22 +29,56 @@ impl<T> RawBuffer<T>{
             capacity: 0,
             layout: Layout::new::<()>(),
             pointer: 0usize,
+            source: Source::Borrowed,
         }
     }
 
     pub unsafe fn new(capacity: usize, zeroed: bool) -> Self {
+        Self::with_alignment(capacity, zeroed, align_of::<T>())
+    }
+
+    /// `align` must be a power of two.
+    pub unsafe fn with_alignment(capacity: usize, zeroed: bool, align: usize) -> Self {
+        Self::with_allocator(capacity, zeroed, align, None)
+    }
+
+    /// Allocate with `allocator`, or the global allocator if `None`.
+    pub unsafe fn with_allocator(capacity: usize, zeroed: bool, align: usize, allocator: Option<&Arc<dyn BufferAllocator>>) -> Self {
         if capacity == 0 { return Self::empty() }
-        let layout = Layout::array::<T>(capacity).unwrap();
+        let layout = Layout::array::<T>(capacity).unwrap().align_to(align).unwrap();
+        let (pointer, source) = match allocator {
+            Some(allocator) => (allocator.allocate(layout, zeroed), Source::Custom(allocator.clone())),
+            None => ({ if zeroed { alloc_zeroed(layout) } else { alloc(layout) } }, Source::Global),
+        };
+        if pointer.is_null() { handle_alloc_error(layout); }
         Self {
             phantom_of_the_opera: PhantomData{},
             capacity,
             layout,
-            pointer: { if zeroed { alloc_zeroed(layout) } else { alloc(layout) } } as usize,
+            pointer: pointer as usize,
+            source,
         }
     }
 
+    /// Wrap `capacity` elements at `pointer`, which won't be freed on drop.
+    ///
+    /// `pointer` must be aligned for `T` and outlive the buffer.
+    pub unsafe fn from_region(pointer: *mut T, capacity: usize) -> Self {
+        Self {
+            phantom_of_the_opera: PhantomData{},
+            capacity,
+            layout: Layout::new::<()>(),
+            pointer: pointer as usize,
+            source: Source::Borrowed,
+        }
+    }
+
+    /// Gets a pointer to the first element, writable even through a shared
+    /// reference to this buffer.
     #[inline]
-    pub fn len(&self) -> usize { self.capacity }
+    pub(crate) fn as_raw_ptr(&self) -> *mut T {
+        self.pointer as *mut T
+    }
 
     #[inline]
     pub(crate) fn get_ref(&self) -> &[T]{
@@ -62,8 +108,11 @@ impl<T> DerefMut for RawBuffer<T>{
 impl<T> Drop for RawBuffer<T>{
     fn drop(&mut self) {
         unsafe {
-            if self.capacity > 0 {
-                dealloc(self.pointer as *mut u8, self.layout);
+            if self.capacity == 0 { return; }
+            match &self.source {
+                Source::Borrowed => {}
+                Source::Global => dealloc(self.pointer as *mut u8, self.layout),
+                Source::Custom(allocator) => allocator.deallocate(self.pointer as *mut u8, self.layout),
             }
         }
     }
@@ -0,0 +1,90 @@
+use std::io::{self, Read};
+use std::ops::DerefMut;
+#[cfg(feature = "read-buf")]
+use std::io::BorrowedBuf;
+#[cfg(feature = "read-buf")]
+use std::mem::MaybeUninit;
+
+/// Fills a pooled byte buffer, such as a `BorrowingSlice<u8>`, from readers
+/// without zeroing it first.
+///
+/// With the `read-buf` feature (nightly only), reads go through
+/// `Read::read_buf` on the uninitialized tail. Otherwise the tail is zeroed
+/// once, on the first read, and never again until the buffer is taken back.
+pub struct PooledReadBuf<B: DerefMut<Target = [u8]>> {
+    buffer: B,
+    filled: usize,
+    /// Number of leading bytes written at least once.
+    #[cfg(not(feature = "read-buf"))]
+    initialized: usize,
+}
+
+impl<B: DerefMut<Target = [u8]>> PooledReadBuf<B> {
+    /// Create a new empty buffer over `buffer`, whose content may be
+    /// uninitialized.
+    pub fn new(buffer: B) -> Self {
+        Self {
+            buffer,
+            filled: 0,
+            #[cfg(not(feature = "read-buf"))]
+            initialized: 0,
+        }
+    }
+
+    /// Read once from `reader` into the unfilled part of the buffer and
+    /// return the number of bytes read, zero once the buffer is full.
+    #[cfg(feature = "read-buf")]
+    pub fn read_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let unfilled = &mut self.buffer[self.filled..];
+        // `read_buf` never reads the uninitialized bytes it is handed
+        let unfilled = unsafe { &mut *(unfilled as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        let mut buf = BorrowedBuf::from(unfilled);
+        reader.read_buf(buf.unfilled())?;
+        let read = buf.len();
+        self.filled += read;
+        Ok(read)
+    }
+
+    /// Read once from `reader` into the unfilled part of the buffer and
+    /// return the number of bytes read, zero once the buffer is full.
+    #[cfg(not(feature = "read-buf"))]
+    pub fn read_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<usize> {
+        if self.initialized < self.buffer.len() {
+            self.buffer[self.initialized..].fill(0);
+            self.initialized = self.buffer.len();
+        }
+        let read = reader.read(&mut self.buffer[self.filled..])?;
+        self.filled += read;
+        Ok(read)
+    }
+
+    /// Gets the bytes read so far.
+    pub fn filled(&self) -> &[u8] {
+        &self.buffer[..self.filled]
+    }
+
+    /// Gets the number of bytes read so far.
+    pub fn len(&self) -> usize {
+        self.filled
+    }
+
+    /// Check whether nothing was read yet.
+    pub fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Gets the number of bytes that can still be read.
+    pub fn remaining(&self) -> usize {
+        self.buffer.len() - self.filled
+    }
+
+    /// Forget the bytes read so far.
+    pub fn clear(&mut self) {
+        self.filled = 0;
+    }
+
+    /// Take back the buffer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
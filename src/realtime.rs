@@ -0,0 +1,166 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::pool::ArrayPoolError;
+use crate::raw_buffer::RawBuffer;
+
+struct RealtimeClass<T> {
+    capacity: usize,
+    buffers: Box<[RawBuffer<T>]>,
+    taken: Box<[AtomicBool]>,
+}
+
+/// A pool whose arrays are all allocated up front, for real-time threads.
+///
+/// After construction, `try_rent` and returning an array never lock, never
+/// allocate and never drop anything: each size class is a fixed set of
+/// slots claimed with a single atomic operation. Arrays are only freed when
+/// the pool itself is dropped.
+pub struct RealtimePool<T: Copy + Default> {
+    classes: Vec<RealtimeClass<T>>,
+}
+
+/// An array rented from a `RealtimePool`, returned to it on drop.
+///
+/// Its elements keep the values written by the previous renter.
+pub struct RealtimeBuffer<'a, T: Copy + Default> {
+    class: &'a RealtimeClass<T>,
+    index: usize,
+}
+
+impl<T: Copy + Default> RealtimeClass<T> {
+    fn new<F: FnMut() -> RawBuffer<T>>(capacity: usize, count: usize, mut allocate: F) -> Self {
+        let buffers = (0..count)
+            .map(|_| unsafe {
+                let mut buffer = allocate();
+                for elem in buffer.iter_mut() {
+                    std::ptr::write(elem, T::default());
+                }
+                buffer
+            })
+            .collect();
+        Self {
+            capacity,
+            buffers,
+            taken: (0..count).map(|_| AtomicBool::new(false)).collect(),
+        }
+    }
+
+    fn try_claim(&self) -> Option<usize> {
+        self.taken.iter().position(|taken| {
+            taken.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok()
+        })
+    }
+
+    fn available(&self) -> usize {
+        self.taken.iter().filter(|taken| !taken.load(Ordering::Relaxed)).count()
+    }
+}
+
+fn merge_classes(classes: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut counts: Vec<(usize, usize)> = vec![];
+    for (capacity, count) in classes {
+        let capacity = capacity.next_power_of_two();
+        match counts.iter_mut().find(|(existing, _)| *existing == capacity) {
+            Some((_, total)) => *total += count,
+            None => counts.push((capacity, *count)),
+        }
+    }
+    counts.sort();
+    counts
+}
+
+impl<T: Copy + Default> RealtimePool<T> {
+    /// Create a new pool, allocating `count` arrays for each
+    /// `(capacity, count)` pair of `classes`.
+    ///
+    /// Capacities are rounded up to a power of two and merged if equal.
+    pub fn with_classes(classes: &[(usize, usize)]) -> Self {
+        Self {
+            classes: merge_classes(classes).into_iter()
+                .map(|(capacity, count)| {
+                    RealtimeClass::new(capacity, count, || unsafe { RawBuffer::new(capacity, false) })
+                })
+                .collect(),
+        }
+    }
+
+    /// Create a new pool carving every array out of `region` instead of
+    /// using the global allocator, see `with_classes`. For the recycling
+    /// logic of an `ArrayPool` over a region, see `ArrayPool::with_backing`.
+    ///
+    /// Fails if `region` can't hold every array.
+    pub fn with_backing(region: &'static mut [u8], classes: &[(usize, usize)]) -> Result<Self, ArrayPoolError> {
+        let classes = merge_classes(classes);
+        let base = region.as_mut_ptr();
+        let align_up = |offset: usize| {
+            let address = (base as usize).checked_add(offset)?;
+            Some(address.checked_next_multiple_of(align_of::<T>())? - base as usize)
+        };
+        let mut offset = align_up(0).ok_or(ArrayPoolError::RegionTooSmall)?;
+        let mut layout = vec![];
+        for (capacity, count) in &classes {
+            let mut starts = vec![];
+            for _ in 0..*count {
+                starts.push(offset);
+                offset = capacity.checked_mul(size_of::<T>())
+                    .and_then(|bytes| offset.checked_add(bytes))
+                    .and_then(align_up)
+                    .ok_or(ArrayPoolError::RegionTooSmall)?;
+            }
+            layout.push(starts);
+        }
+        if offset > region.len() { return Err(ArrayPoolError::RegionTooSmall); }
+
+        Ok(Self {
+            classes: classes.into_iter().zip(layout)
+                .map(|((capacity, count), starts)| {
+                    let mut starts = starts.into_iter();
+                    RealtimeClass::new(capacity, count, || unsafe {
+                        RawBuffer::from_region(base.add(starts.next().unwrap()) as *mut T, capacity)
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    /// Rent a free array with `minimum_capacity` without locking or
+    /// allocating, from the smallest size class that has one.
+    ///
+    /// Returns `None` if every fitting array is rented.
+    pub fn try_rent(&self, minimum_capacity: usize) -> Option<RealtimeBuffer<'_, T>> {
+        self.classes.iter()
+            .filter(|class| class.capacity >= minimum_capacity)
+            .find_map(|class| class.try_claim().map(|index| RealtimeBuffer { class, index }))
+    }
+
+    /// Gets the number of arrays that can be rented with `minimum_capacity`.
+    pub fn available(&self, minimum_capacity: usize) -> usize {
+        self.classes.iter()
+            .filter(|class| class.capacity >= minimum_capacity)
+            .map(RealtimeClass::available)
+            .sum()
+    }
+}
+
+impl<T: Copy + Default> Drop for RealtimeBuffer<'_, T> {
+    fn drop(&mut self) {
+        self.class.taken[self.index].store(false, Ordering::Release);
+    }
+}
+
+impl<T: Copy + Default> Deref for RealtimeBuffer<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.class.buffers[self.index].deref()
+    }
+}
+
+impl<T: Copy + Default> DerefMut for RealtimeBuffer<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let buffer = &self.class.buffers[self.index];
+        // Claiming the slot grants exclusive access to its array
+        unsafe { std::slice::from_raw_parts_mut(buffer.as_raw_ptr(), buffer.len()) }
+    }
+}
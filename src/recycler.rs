@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::backend::{rent_default, PoolBackend};
+use crate::pool::{ArrayPool, ArrayPoolError};
+
+struct RecyclerState<B> {
+    queue: VecDeque<B>,
+    live: usize,
+}
+
+/// The state of a `BufferRecycler` its buffers hand their array back to.
+struct RecyclerShared<B> {
+    state: Mutex<RecyclerState<B>>,
+    available: Condvar,
+}
+
+impl<B> RecyclerShared<B> {
+    fn release(&self, buffer: B) {
+        self.state.lock().unwrap().queue.push_back(buffer);
+        self.available.notify_one();
+    }
+
+    fn forget_one(&self) {
+        self.state.lock().unwrap().live -= 1;
+        self.available.notify_one();
+    }
+}
+
+/// Hands out a bounded number of buffers of the same capacity to producers
+/// and takes them back from consumers, for producer/consumer pipelines.
+///
+/// At most `max_buffers` buffers exist at once. Released buffers are queued
+/// for reuse and keep their last content.
+pub struct BufferRecycler<T: Copy + Default, P: PoolBackend<T> = ArrayPool<T>> {
+    pool: Arc<P>,
+    capacity: usize,
+    max_buffers: usize,
+    shared: Arc<RecyclerShared<P::Buffer>>,
+}
+
+/// A buffer handed out by a `BufferRecycler`, queued back for reuse when
+/// dropped.
+pub struct RecycledBuffer<B> {
+    buffer: Option<B>,
+    shared: Arc<RecyclerShared<B>>,
+}
+
+impl<T: Copy + Default, P: PoolBackend<T>> BufferRecycler<T, P> {
+    /// Create a new recycler handing out at most `max_buffers` buffers of
+    /// at least `capacity` elements.
+    pub fn create(pool: Arc<P>, capacity: usize, max_buffers: usize) -> Self {
+        Self {
+            pool,
+            capacity,
+            max_buffers,
+            shared: Arc::new(RecyclerShared {
+                state: Mutex::new(RecyclerState {
+                    queue: VecDeque::new(),
+                    live: 0,
+                }),
+                available: Condvar::new(),
+            }),
+        }
+    }
+
+    fn wrap(&self, buffer: P::Buffer) -> RecycledBuffer<P::Buffer> {
+        RecycledBuffer {
+            buffer: Some(buffer),
+            shared: self.shared.clone(),
+        }
+    }
+
+    fn rent_new(&self) -> Result<RecycledBuffer<P::Buffer>, ArrayPoolError> {
+        match rent_default(self.pool.as_ref(), self.capacity) {
+            Ok(buffer) => Ok(self.wrap(buffer)),
+            Err(error) => {
+                self.shared.forget_one();
+                Err(error)
+            }
+        }
+    }
+
+    /// Take a queued buffer or rent a new one, blocking until a buffer is
+    /// released if `max_buffers` are already out.
+    pub fn acquire(&self) -> Result<RecycledBuffer<P::Buffer>, ArrayPoolError> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(buffer) = state.queue.pop_front() {
+                return Ok(self.wrap(buffer));
+            }
+            if state.live < self.max_buffers {
+                state.live += 1;
+                drop(state);
+                return self.rent_new();
+            }
+            state = self.shared.available.wait(state).unwrap();
+        }
+    }
+
+    /// Take a queued buffer or rent a new one, `None` if `max_buffers` are
+    /// already out.
+    pub fn try_acquire(&self) -> Result<Option<RecycledBuffer<P::Buffer>>, ArrayPoolError> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(buffer) = state.queue.pop_front() {
+            return Ok(Some(self.wrap(buffer)));
+        }
+        if state.live < self.max_buffers {
+            state.live += 1;
+            drop(state);
+            return self.rent_new().map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Queue `buffer` for reuse, waking up a blocked `acquire`. Same as
+    /// dropping it.
+    pub fn release(&self, buffer: RecycledBuffer<P::Buffer>) {
+        drop(buffer);
+    }
+
+    /// Return `buffer` to the pool instead of queuing it, making room for a
+    /// new one.
+    pub fn retire(&self, mut buffer: RecycledBuffer<P::Buffer>) {
+        if let Some(buffer) = buffer.buffer.take() {
+            self.pool.return_buffer(buffer);
+        }
+        self.shared.forget_one();
+    }
+
+    /// Gets the number of buffers handed out or queued.
+    pub fn live(&self) -> usize {
+        self.shared.state.lock().unwrap().live
+    }
+
+    /// Gets the number of buffers queued for reuse.
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().queue.len()
+    }
+}
+
+impl<B> Drop for RecycledBuffer<B> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.shared.release(buffer);
+        }
+    }
+}
+
+impl<B: Deref> Deref for RecycledBuffer<B> {
+    type Target = B::Target;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.as_ref().unwrap()
+    }
+}
+
+impl<B: DerefMut> DerefMut for RecycledBuffer<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer.as_mut().unwrap()
+    }
+}
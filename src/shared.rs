@@ -0,0 +1,73 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::ptr::drop_in_place;
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+
+struct Frozen<T, P: PoolBackend<T>> {
+    pool: Arc<P>,
+    buffer: Option<P::Buffer>,
+    length: usize,
+}
+
+/// A read-only pooled slice shared between threads, see
+/// `PooledVec::snapshot`.
+///
+/// Cloning only bumps a reference count. The elements are dropped and the
+/// buffer returned to its pool once the last clone is dropped.
+pub struct SharedSlice<T, P: PoolBackend<T> = ArrayPool<T>> {
+    frozen: Arc<Frozen<T, P>>,
+}
+
+impl<T, P: PoolBackend<T>> SharedSlice<T, P> {
+    /// Share the first `length` elements of `buffer`, which must be
+    /// initialized, returning `buffer` to `pool` once the last clone is dropped.
+    pub(crate) fn new(pool: Arc<P>, buffer: Option<P::Buffer>, length: usize) -> Self {
+        Self { frozen: Arc::new(Frozen { pool, buffer, length }) }
+    }
+
+    /// Gets the number of clones of this slice, itself included.
+    pub fn ref_count(&self) -> usize {
+        Arc::strong_count(&self.frozen)
+    }
+}
+
+impl<T, P: PoolBackend<T>> Drop for Frozen<T, P> {
+    fn drop(&mut self) {
+        if let Some(mut buffer) = self.buffer.take() {
+            unsafe { drop_in_place(&mut buffer[..self.length]); }
+            self.pool.return_buffer(buffer);
+        }
+    }
+}
+
+impl<T, P: PoolBackend<T>> Clone for SharedSlice<T, P> {
+    fn clone(&self) -> Self {
+        Self { frozen: self.frozen.clone() }
+    }
+}
+
+impl<T, P: PoolBackend<T>> Deref for SharedSlice<T, P> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.frozen.buffer {
+            Some(buffer) => &buffer[..self.frozen.length],
+            None => &[],
+        }
+    }
+}
+
+impl<T, P: PoolBackend<T>> AsRef<[T]> for SharedSlice<T, P> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: Debug, P: PoolBackend<T>> Debug for SharedSlice<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+use crate::vec::PooledVec;
+
+/// A key of a `PooledSlotMap` entry.
+///
+/// Keys stay valid until their entry is removed, after which they never
+/// match the entries reusing the same slot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SlotKey {
+    index: u32,
+    generation: u32,
+}
+
+/// A map handing out a `SlotKey` for each inserted value, kept as slots
+/// of values, slot generations and free slots in pooled vectors.
+///
+/// Inserting, removing and looking up take constant time. Removed slots
+/// are reused by later inserts, bumping their generation.
+pub struct PooledSlotMap<V, PV: PoolBackend<Option<V>> = ArrayPool<Option<V>>, PG: PoolBackend<u32> = ArrayPool<u32>> {
+    values: PooledVec<Option<V>, PV>,
+    generations: PooledVec<u32, PG>,
+    free: PooledVec<u32, PG>,
+}
+
+impl<V, PV: PoolBackend<Option<V>>, PG: PoolBackend<u32>> PooledSlotMap<V, PV, PG> {
+    /// Create a new map renting slots from `value_pool`, generations and
+    /// free slots from `index_pool`.
+    pub fn create(value_pool: Arc<PV>, index_pool: Arc<PG>) -> Self {
+        Self {
+            values: PooledVec::create(value_pool),
+            generations: PooledVec::create(index_pool.clone()),
+            free: PooledVec::create(index_pool),
+        }
+    }
+
+    /// Gets the number of entries.
+    pub fn len(&self) -> usize {
+        self.values.len() - self.free.len()
+    }
+
+    /// Check whether this map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert `value` in a free slot and return its key.
+    ///
+    /// Panics if the map already holds `u32::MAX` slots.
+    pub fn insert(&mut self, value: V) -> SlotKey {
+        if let Some(index) = self.free.pop() {
+            self.values[index as usize] = Some(value);
+            return SlotKey { index, generation: self.generations[index as usize] };
+        }
+        let index = u32::try_from(self.values.len()).expect("Slot map is full");
+        self.values.push(Some(value));
+        self.generations.push(0);
+        SlotKey { index, generation: 0 }
+    }
+
+    fn slot(&self, key: SlotKey) -> Option<usize> {
+        let index = key.index as usize;
+        (self.generations.at(index) == Some(&key.generation)).then_some(index)
+    }
+
+    /// Remove the entry of `key` and return its value.
+    pub fn remove(&mut self, key: SlotKey) -> Option<V> {
+        let index = self.slot(key)?;
+        let value = self.values[index].take()?;
+        // Outdate every key of this slot
+        self.generations[index] = key.generation.wrapping_add(1);
+        self.free.push(key.index);
+        Some(value)
+    }
+
+    /// Gets the value of `key`.
+    pub fn get(&self, key: SlotKey) -> Option<&V> {
+        self.values[self.slot(key)?].as_ref()
+    }
+
+    /// Gets a mutable reference to the value of `key`.
+    pub fn get_mut(&mut self, key: SlotKey) -> Option<&mut V> {
+        let index = self.slot(key)?;
+        self.values[index].as_mut()
+    }
+
+    /// Check whether this map holds `key`.
+    pub fn contains_key(&self, key: SlotKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove every entry, outdating all keys handed out so far.
+    pub fn clear(&mut self) {
+        self.free.clear();
+        for index in (0..self.values.len()).rev() {
+            if self.values[index].take().is_some() {
+                self.generations[index] = self.generations[index].wrapping_add(1);
+            }
+            self.free.push(index as u32);
+        }
+    }
+
+    /// Iterate over the entries in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (SlotKey, &V)> {
+        self.values.iter().zip(self.generations.iter()).enumerate()
+            .filter_map(|(index, (value, generation))| {
+                Some((SlotKey { index: index as u32, generation: *generation }, value.as_ref()?))
+            })
+    }
+}
+
+impl<V, PV: PoolBackend<Option<V>>, PG: PoolBackend<u32>> Drop for PooledSlotMap<V, PV, PG> {
+    fn drop(&mut self) {
+        // Pooled vectors don't drop their elements
+        self.values.clear();
+    }
+}
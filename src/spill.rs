@@ -0,0 +1,151 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::mem::{size_of, MaybeUninit};
+use std::path::PathBuf;
+use std::process;
+use std::slice;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backend::PoolBackend;
+use crate::pool::ArrayPool;
+use crate::vec::PooledVec;
+
+static SPILL_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Types that can be written to and read back from disk as raw bytes.
+///
+/// # Safety
+///
+/// The type must have no padding, no pointers and accept any bit pattern.
+pub unsafe trait Plain: Copy {}
+
+macro_rules! impl_plain {
+    ($($t:ty),*) => { $(unsafe impl Plain for $t {})* };
+}
+
+impl_plain!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// A vector that keeps up to `threshold` elements in a pooled array and
+/// spills the rest to a temporary file, streaming them back on iteration.
+pub struct SpillVec<T: Plain, P: PoolBackend<T> = ArrayPool<T>> {
+    memory: PooledVec<T, P>,
+    threshold: usize,
+    spilled: usize,
+    file: Option<(PathBuf, File)>,
+}
+
+/// Iterates a `SpillVec`, reading the spilled elements first.
+pub struct SpillIter<'a, T: Plain> {
+    reader: Option<BufReader<File>>,
+    remaining: usize,
+    memory: slice::Iter<'a, T>,
+}
+
+impl<T: Plain, P: PoolBackend<T>> SpillVec<T, P> {
+    /// Create a new vector spilling to disk once it holds `threshold`
+    /// elements in memory.
+    pub fn with_threshold(pool: Arc<P>, threshold: usize) -> Self {
+        Self {
+            memory: PooledVec::create(pool),
+            threshold: threshold.max(1),
+            spilled: 0,
+            file: None,
+        }
+    }
+
+    fn open_spill_file(&mut self) -> io::Result<()> {
+        if self.file.is_none() {
+            let id = SPILL_FILES.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!("array_pool-{}-{id}.spill", process::id()));
+            let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+            self.file = Some((path, file));
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.open_spill_file()?;
+        let count = self.memory.len();
+        let bytes = unsafe {
+            slice::from_raw_parts(self.memory.as_ptr() as *const u8, count * size_of::<T>())
+        };
+        if let Some((_, file)) = &mut self.file {
+            file.write_all(bytes)?;
+        }
+        self.spilled += count;
+        self.memory.clear();
+        Ok(())
+    }
+
+    /// Push a new element, spilling the in-memory elements if the
+    /// threshold is reached.
+    pub fn push(&mut self, value: T) -> io::Result<()> {
+        self.memory.push(value);
+        if self.memory.len() >= self.threshold {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Get the length of this vector.
+    pub fn len(&self) -> usize {
+        self.spilled + self.memory.len()
+    }
+
+    /// Check whether this vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of elements stored on disk.
+    pub fn spilled_len(&self) -> usize {
+        self.spilled
+    }
+
+    /// Iterate every element in push order.
+    pub fn iter(&self) -> io::Result<SpillIter<'_, T>> {
+        let reader = match &self.file {
+            Some((path, _)) => Some(BufReader::new(File::open(path)?)),
+            None => None,
+        };
+        Ok(SpillIter {
+            reader,
+            remaining: self.spilled,
+            memory: self.memory.iter(),
+        })
+    }
+
+    /// Remove every element, deleting the spill file.
+    pub fn clear(&mut self) {
+        self.memory.clear();
+        self.spilled = 0;
+        if let Some((path, file)) = self.file.take() {
+            drop(file);
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl<T: Plain, P: PoolBackend<T>> Drop for SpillVec<T, P> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Plain> Iterator for SpillIter<'_, T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return self.memory.next().map(|x| Ok(*x));
+        }
+        let reader = self.reader.as_mut()?;
+        self.remaining -= 1;
+        let mut value = MaybeUninit::<T>::zeroed();
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>())
+        };
+        Some(reader.read_exact(bytes).map(|_| unsafe { value.assume_init() }))
+    }
+}
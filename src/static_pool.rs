@@ -0,0 +1,67 @@
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+use crate::manager::PoolManager;
+use crate::pool::ArrayPool;
+
+/// The process-wide pools of `global_pool`, one per element type.
+static GLOBAL_POOLS: OnceLock<PoolManager> = OnceLock::new();
+
+/// Gets the process-wide pool of `T`, created by `ArrayPool::new` on
+/// first use unless set by `set_global_pool`.
+///
+/// Backs `PooledVec::new` and the `Default` and `FromIterator`
+/// implementations of `PooledVec`.
+pub fn global_pool<T: Send + 'static>() -> Arc<ArrayPool<T>> {
+    GLOBAL_POOLS.get_or_init(PoolManager::new).pool()
+}
+
+/// Make `pool` the process-wide pool of `T` returned by `global_pool`.
+///
+/// Vectors created before keep the pool they were created with.
+pub fn set_global_pool<T: Send + 'static>(pool: Arc<ArrayPool<T>>) {
+    GLOBAL_POOLS.get_or_init(PoolManager::new).set_pool(pool);
+}
+
+/// A process-wide `ArrayPool` for use in a `static`, created on first use.
+///
+/// The pool is built by `ArrayPool::new`, or by a builder function for
+/// a custom configuration.
+pub struct StaticPool<T: Send> {
+    pool: OnceLock<Arc<ArrayPool<T>>>,
+    builder: fn() -> ArrayPool<T>,
+}
+
+impl<T: Send> StaticPool<T> {
+    /// Create a new pool built by `ArrayPool::new` on first use.
+    pub const fn new() -> Self {
+        Self::with_builder(ArrayPool::new)
+    }
+
+    /// Create a new pool built by `builder` on first use.
+    pub const fn with_builder(builder: fn() -> ArrayPool<T>) -> Self {
+        Self {
+            pool: OnceLock::new(),
+            builder,
+        }
+    }
+
+    /// Gets the pool, building it if needed.
+    pub fn get(&self) -> &Arc<ArrayPool<T>> {
+        self.pool.get_or_init(|| Arc::new((self.builder)()))
+    }
+}
+
+impl<T: Send> Default for StaticPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send> Deref for StaticPool<T> {
+    type Target = Arc<ArrayPool<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
@@ -0,0 +1,186 @@
+use std::ops::{Add, AddAssign};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// Usage counters of an array pool.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Number of arrays rented.
+    pub rents: usize,
+    /// Number of arrays returned.
+    pub returns: usize,
+    /// Number of rents that had to allocate a new array.
+    pub allocations: usize,
+    /// Number of rents served by another thread's chain.
+    pub steals: usize,
+    /// Number of returned arrays freed instead of cached.
+    pub discards: usize,
+    /// Number of cached arrays freed after lowering the pool's limits.
+    pub evictions: usize,
+    /// Number of arrays currently cached.
+    pub cached: usize,
+    /// Total size in bytes of the currently cached arrays.
+    pub cached_bytes: usize,
+    /// Number of lock acquisitions that had to wait for another thread.
+    ///
+    /// This and the following timings stay at zero unless the
+    /// `stats-timing` feature is enabled.
+    pub contended: usize,
+    /// Time spent waiting on the per-thread chain locks.
+    pub local_wait: Duration,
+    /// Time spent waiting on the lock of the chain registry.
+    pub registry_wait: Duration,
+    /// Time spent looking for an array in other threads' chains.
+    pub steal_time: Duration,
+}
+
+/// Usage of the arrays rented under one tag, see `ArrayPool::tag_stats`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagStats {
+    /// Number of arrays rented under the tag.
+    pub rents: usize,
+    /// Number of arrays currently rented under the tag.
+    pub held: usize,
+    /// Total size in bytes of the arrays currently rented under the tag.
+    pub held_bytes: usize,
+}
+
+/// Rent counters of one thread, see `ArrayPool::thread_stats`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThreadStats {
+    /// Identifier of the thread.
+    pub id: ThreadId,
+    /// Name of the thread, if it has one.
+    pub name: Option<String>,
+    /// Whether the thread is still running.
+    pub alive: bool,
+    /// Number of arrays the thread rented.
+    pub rents: usize,
+    /// Number of these rents served by the thread's own chain.
+    pub hits: usize,
+    /// Number of these rents served by another thread's chain.
+    pub steals: usize,
+    /// Number of these rents that had to allocate a new array.
+    pub allocations: usize,
+}
+
+impl ThreadStats {
+    /// Gets the number of rents the thread's own chain couldn't serve.
+    ///
+    /// Counters read during a rent may briefly show more hits than rents.
+    pub fn misses(&self) -> usize {
+        self.rents.saturating_sub(self.hits)
+    }
+}
+
+/// What a compaction changed in the cache, see `ArrayPool::compact`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Compaction {
+    /// Number of cached arrays freed.
+    pub freed: usize,
+    /// Number of arrays allocated and cached ahead of demand.
+    pub allocated: usize,
+}
+
+impl Add for Compaction {
+    type Output = Compaction;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Compaction {
+            freed: self.freed + rhs.freed,
+            allocated: self.allocated + rhs.allocated,
+        }
+    }
+}
+
+/// Usage counters of a pool taken at some instant, see
+/// `ArrayPool::stats_snapshot`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// When the counters were read.
+    pub taken_at: Instant,
+    /// Number of times the counters were reset before they were read.
+    pub resets: usize,
+    /// The counters.
+    pub stats: PoolStats,
+}
+
+/// Activity of a pool between two snapshots, see `StatsSnapshot::delta`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StatsDelta {
+    /// Time between the two snapshots.
+    pub elapsed: Duration,
+    /// Increase of each counter over the interval. `cached` and
+    /// `cached_bytes` hold their value at the end of the interval.
+    pub stats: PoolStats,
+}
+
+impl PoolStats {
+    /// Number of rented arrays that have not been returned yet.
+    pub fn outstanding(&self) -> usize {
+        self.rents.saturating_sub(self.returns)
+    }
+}
+
+impl StatsSnapshot {
+    /// Gets the activity since `earlier`.
+    ///
+    /// Counters reset in between count from zero.
+    pub fn delta(&self, earlier: &StatsSnapshot) -> StatsDelta {
+        let reset = self.resets != earlier.resets;
+        let (now, then) = (&self.stats, &earlier.stats);
+        let since = |now: usize, then: usize| if reset { now } else { now.saturating_sub(then) };
+        let since_time = |now: Duration, then: Duration| if reset { now } else { now.saturating_sub(then) };
+        StatsDelta {
+            elapsed: self.taken_at.saturating_duration_since(earlier.taken_at),
+            stats: PoolStats {
+                rents: since(now.rents, then.rents),
+                returns: since(now.returns, then.returns),
+                allocations: since(now.allocations, then.allocations),
+                steals: since(now.steals, then.steals),
+                discards: since(now.discards, then.discards),
+                evictions: since(now.evictions, then.evictions),
+                cached: now.cached,
+                cached_bytes: now.cached_bytes,
+                contended: since(now.contended, then.contended),
+                local_wait: since_time(now.local_wait, then.local_wait),
+                registry_wait: since_time(now.registry_wait, then.registry_wait),
+                steal_time: since_time(now.steal_time, then.steal_time),
+            },
+        }
+    }
+}
+
+impl StatsDelta {
+    /// Gets `count` per second over the interval, e.g. `delta.per_second(delta.stats.rents)`.
+    pub fn per_second(&self, count: usize) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 { 0.0 } else { count as f64 / seconds }
+    }
+}
+
+impl Add for PoolStats {
+    type Output = PoolStats;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for PoolStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.rents += rhs.rents;
+        self.returns += rhs.returns;
+        self.allocations += rhs.allocations;
+        self.steals += rhs.steals;
+        self.discards += rhs.discards;
+        self.evictions += rhs.evictions;
+        self.cached += rhs.cached;
+        self.cached_bytes += rhs.cached_bytes;
+        self.contended += rhs.contended;
+        self.local_wait += rhs.local_wait;
+        self.registry_wait += rhs.registry_wait;
+        self.steal_time += rhs.steal_time;
+    }
+}
@@ -0,0 +1,224 @@
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::backend::PoolBackend;
+use crate::pool::{ArrayPool, ArrayPoolError};
+
+#[derive(Default)]
+struct Counters {
+    rents: AtomicUsize,
+    returns: AtomicUsize,
+    failures: AtomicUsize,
+    fail_next: AtomicUsize,
+    fail_all: AtomicBool,
+    /// Rents left until the one made to fail, 0 if none.
+    fail_at: AtomicUsize,
+    /// Capacity above which rents fail, 0 if none.
+    fail_above: AtomicUsize,
+    error: Mutex<Option<ArrayPoolError>>,
+}
+
+/// A pool wrapper that records every rent and return going through it,
+/// meant for testing code built on top of a `PoolBackend`.
+///
+/// Expanding or shrinking a buffer counts as one rent and one return.
+pub struct CountingPool<P> {
+    inner: P,
+    counters: Arc<Counters>
+}
+
+/// A buffer rented from a `CountingPool`.
+pub struct CountedBuffer<B> {
+    buffer: B,
+    counters: Arc<Counters>
+}
+
+impl<B> CountedBuffer<B> {
+    fn into_inner(self) -> B {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            drop(ptr::read(&this.counters));
+            ptr::read(&this.buffer)
+        }
+    }
+}
+
+impl<B> Drop for CountedBuffer<B> {
+    fn drop(&mut self) {
+        self.counters.returns.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl<B: Deref> Deref for CountedBuffer<B> {
+    type Target = B::Target;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.deref()
+    }
+}
+
+impl<B: DerefMut> DerefMut for CountedBuffer<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer.deref_mut()
+    }
+}
+
+impl<P> CountingPool<P> {
+    /// Wrap `inner` in a new `CountingPool`.
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    /// Gets the wrapped pool.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Gets the number of buffers rented so far.
+    pub fn rents(&self) -> usize {
+        self.counters.rents.load(Ordering::SeqCst)
+    }
+
+    /// Gets the number of buffers returned so far.
+    pub fn returns(&self) -> usize {
+        self.counters.returns.load(Ordering::SeqCst)
+    }
+
+    /// Gets the number of rents that were made to fail.
+    pub fn failures(&self) -> usize {
+        self.counters.failures.load(Ordering::SeqCst)
+    }
+
+    /// Gets the number of rented buffers that have not been returned yet.
+    pub fn outstanding(&self) -> usize {
+        self.rents().saturating_sub(self.returns())
+    }
+
+    /// Make the next `count` rents fail.
+    pub fn fail_next_rents(&self, count: usize) {
+        self.counters.fail_next.store(count, Ordering::SeqCst);
+    }
+
+    /// Make every rent fail until disabled.
+    pub fn fail_rents(&self, enabled: bool) {
+        self.counters.fail_all.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Make the `n`th rent from now fail, counting from 1, or none if 0.
+    pub fn fail_nth_rent(&self, n: usize) {
+        self.counters.fail_at.store(n, Ordering::SeqCst);
+    }
+
+    /// Make rents of more than `capacity` elements fail with
+    /// `MaxChunkSizeNotSufficient`, as if the pool had no larger size
+    /// class, or lift the limit if `None`.
+    pub fn fail_rents_above(&self, capacity: Option<usize>) {
+        self.counters.fail_above.store(capacity.map_or(0, |capacity| capacity.max(1)), Ordering::SeqCst);
+    }
+
+    /// Set the error of the rents made to fail, `MaxChunkSizeNotSufficient`
+    /// by default. `Exhausted` simulates a frozen pool running out of
+    /// arrays, `AllocationForbidden` a thread forbidden to allocate.
+    pub fn set_failure(&self, error: ArrayPoolError) {
+        *self.counters.error.lock().unwrap() = Some(error);
+    }
+
+    /// Run `section` and panic if it rented any buffer, including through
+    /// expanding or shrinking one.
+    pub fn assert_no_rents<R, F: FnOnce() -> R>(&self, section: F) -> R {
+        let before = self.rents();
+        let result = section();
+        let rented = self.rents() - before;
+        assert_eq!(rented, 0, "{rented} buffer(s) were rented in a rent-free section");
+        result
+    }
+
+    /// Fail the rent about to be made with `capacity` if asked to. The rent
+    /// is counted by `wrap` once the inner pool served it.
+    fn begin_rent(&self, capacity: usize) -> Result<(), ArrayPoolError> {
+        let countdown = |counter: &AtomicUsize| counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| x.checked_sub(1));
+        let nth = countdown(&self.counters.fail_at) == Ok(1);
+        let next = countdown(&self.counters.fail_next).is_ok();
+        let fail_above = self.counters.fail_above.load(Ordering::SeqCst);
+        if fail_above != 0 && capacity > fail_above {
+            self.counters.failures.fetch_add(1, Ordering::SeqCst);
+            return Err(ArrayPoolError::MaxChunkSizeNotSufficient);
+        }
+        if self.counters.fail_all.load(Ordering::SeqCst) || next || nth {
+            self.counters.failures.fetch_add(1, Ordering::SeqCst);
+            let error = *self.counters.error.lock().unwrap();
+            return Err(error.unwrap_or(ArrayPoolError::MaxChunkSizeNotSufficient));
+        }
+        Ok(())
+    }
+
+    fn wrap<B>(&self, buffer: B) -> CountedBuffer<B> {
+        self.counters.rents.fetch_add(1, Ordering::SeqCst);
+        CountedBuffer {
+            buffer,
+            counters: self.counters.clone(),
+        }
+    }
+}
+
+impl<T: Send> CountingPool<ArrayPool<T>> {
+    /// Run `section` and panic if the wrapped pool allocated any array.
+    /// Unlike `assert_no_rents`, rents served by cached arrays are allowed.
+    pub fn assert_no_allocations<R, F: FnOnce() -> R>(&self, section: F) -> R {
+        let before = self.inner.stats().allocations;
+        let result = section();
+        let allocated = self.inner.stats().allocations - before;
+        assert_eq!(allocated, 0, "{allocated} array(s) were allocated in an allocation-free section");
+        result
+    }
+}
+
+impl<T, P: PoolBackend<T>> PoolBackend<T> for CountingPool<P> {
+    type Buffer = CountedBuffer<P::Buffer>;
+
+    unsafe fn rent_uninitialized(&self, minimum_capacity: usize) -> Result<Self::Buffer, ArrayPoolError> {
+        self.begin_rent(minimum_capacity)?;
+        self.inner.rent_uninitialized(minimum_capacity).map(|buffer| self.wrap(buffer))
+    }
+
+    unsafe fn rent_minimum_uninitialized(&self) -> Result<Self::Buffer, ArrayPoolError> {
+        self.begin_rent(self.inner.min_size())?;
+        self.inner.rent_minimum_uninitialized().map(|buffer| self.wrap(buffer))
+    }
+
+    fn return_buffer(&self, buffer: Self::Buffer) {
+        self.inner.return_buffer(buffer.into_inner());
+        self.counters.returns.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe fn expand_buffer(&self, buffer: Self::Buffer) -> Result<Self::Buffer, ArrayPoolError> {
+        self.begin_rent(buffer.len() * 2)?;
+        let expanded = self.inner.expand_buffer(buffer.into_inner()).map(|buffer| self.wrap(buffer));
+        self.counters.returns.fetch_add(1, Ordering::SeqCst);
+        expanded
+    }
+
+    unsafe fn shrink_buffer(&self, buffer: Self::Buffer) -> Self::Buffer {
+        self.counters.returns.fetch_add(1, Ordering::SeqCst);
+        self.wrap(self.inner.shrink_buffer(buffer.into_inner()))
+    }
+
+    fn tag_buffer(&self, buffer: &mut Self::Buffer, tag: &'static str) {
+        self.inner.tag_buffer(&mut buffer.buffer, tag)
+    }
+
+    fn min_size(&self) -> usize {
+        self.inner.min_size()
+    }
+
+    fn max_size(&self) -> usize {
+        self.inner.max_size()
+    }
+}
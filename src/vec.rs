@@ -1,36 +1,110 @@
-use std::fmt::{Display, Formatter};
+use std::borrow::{Borrow, BorrowMut};
+use std::fmt::{Debug, Display, Formatter};
 use std::mem::swap;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Index, IndexMut};
 use std::ptr::drop_in_place;
+use std::slice::SliceIndex;
 use std::sync::Arc;
-use crate::pool::{ArrayPool, BorrowingSlice};
+use crate::backend::PoolBackend;
+use crate::ext::IteratorExt;
+use crate::guard::{fill_uninitialized, InitGuard};
+use crate::pool::{ArrayPool, ArrayPoolError};
+use crate::shared::SharedSlice;
+use crate::static_pool::global_pool;
 
 /// A vector implementation that uses pooled arrays.
-pub struct PooledVec<T: Send> {
+///
+/// Buffers are rented from `P`, which is an `ArrayPool` by default.
+pub struct PooledVec<T, P: PoolBackend<T> = ArrayPool<T>> {
     empty_buffer: [T; 0],
-    pool: Arc<ArrayPool<T>>,
-    buffer: Option<BorrowingSlice<T>>,
-    length: usize
+    pool: Arc<P>,
+    buffer: Option<P::Buffer>,
+    length: usize,
+    tag: Option<&'static str>
 }
 
-impl<T: Send> PooledVec<T> {
+/// An element `PooledVec::try_push` couldn't push, with the reason.
+#[derive(Debug)]
+pub struct PushError<T> {
+    /// The element that wasn't pushed.
+    pub value: T,
+    /// Why no buffer large enough could be rented.
+    pub error: ArrayPoolError,
+}
+
+impl<T, P: PoolBackend<T>> PooledVec<T, P> {
     /// Create a new vector.
-    pub fn create(pool: Arc<ArrayPool<T>>) -> Self {
+    pub fn create(pool: Arc<P>) -> Self {
         Self{
             empty_buffer: [],
             pool,
             buffer: None,
             length: 0,
+            tag: None,
         }
     }
 
-    fn push_with_buffer(&mut self, mut buffer: BorrowingSlice<T>, value: T) {
+    /// Create a new vector whose buffers are attributed to `tag` in the
+    /// usage statistics of the pool, see `ArrayPool::tag_stats`.
+    pub fn create_tagged(pool: Arc<P>, tag: &'static str) -> Self {
+        Self { tag: Some(tag), ..Self::create(pool) }
+    }
+
+    /// Gets the tag of this vector's buffers.
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// Rent a buffer of at least `minimum_capacity`, or of the smallest
+    /// capacity if 0, with the tag of this vector.
+    unsafe fn rent_buffer(&self, minimum_capacity: usize) -> Result<P::Buffer, ArrayPoolError> {
+        let mut buffer = match minimum_capacity {
+            0 => self.pool.rent_minimum_uninitialized(),
+            _ => self.pool.rent_uninitialized(minimum_capacity),
+        }?;
+        if let Some(tag) = self.tag {
+            self.pool.tag_buffer(&mut buffer, tag);
+        }
+        Ok(buffer)
+    }
+
+    /// Create a new vector able to hold `capacity` elements without
+    /// expanding its buffer.
+    pub fn with_capacity(pool: Arc<P>, capacity: usize) -> Self {
+        let buffer = match capacity {
+            0 => None,
+            _ => Some(unsafe { pool.rent_uninitialized(capacity) }.expect("Could not request buffer")),
+        };
+        Self{
+            empty_buffer: [],
+            pool,
+            buffer,
+            length: 0,
+            tag: None,
+        }
+    }
+
+    /// Create a new vector holding `count` clones of `value`.
+    pub fn from_elem(pool: Arc<P>, value: T, count: usize) -> Self where T: Clone {
+        let mut vec = Self::with_capacity(pool, count);
+        if let Some(buffer) = &mut vec.buffer {
+            unsafe { fill_uninitialized(&mut buffer[..count], value); }
+            vec.length = count;
+        }
+        vec
+    }
+
+    fn push_with_buffer(&mut self, mut buffer: P::Buffer, value: T) {
         let index = self.length;
         let buffer_size = buffer.len();
         if index >= buffer_size {
-            unsafe {
-                buffer = self.pool.expand_buffer(buffer)
-                    .expect("Could not request buffer");
+            match unsafe { self.pool.expand_buffer(buffer) } {
+                Ok(expanded) => buffer = expanded,
+                Err(_) => {
+                    // The failed expansion took the elements along with the buffer
+                    self.length = 0;
+                    panic!("Could not request buffer");
+                }
             }
         }
         unsafe { std::ptr::write(&mut buffer[index], value); }
@@ -40,22 +114,183 @@ impl<T: Send> PooledVec<T> {
 
     /// Push a new element. Expand the internal buffer if needed.
     pub fn push(&mut self, value: T) {
-        let mut curr: Option<BorrowingSlice<T>> = None;
+        let mut curr: Option<P::Buffer> = None;
         swap(&mut curr, &mut self.buffer);
         if let Some(buffer) = curr {
             self.push_with_buffer(buffer, value);
-        } else if let Ok(buffer) = unsafe { self.pool.rent_or_create_minimum_uninitialized(false) } {
+        } else if let Ok(buffer) = unsafe { self.rent_buffer(0) } {
             self.push_with_buffer(buffer, value);
         } else {
             panic!("Could not borrow a buffer from given array pool");
         }
     }
 
+    /// Push a new element, expanding the internal buffer if needed.
+    ///
+    /// Unlike `push`, hand `value` back if the pool can't supply a bigger
+    /// buffer. The vector is left untouched in that case.
+    pub fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        if let Err(error) = self.try_reserve(1) {
+            return Err(PushError { value, error });
+        }
+        let index = self.length;
+        let buffer = self.buffer.as_mut().unwrap();
+        unsafe { std::ptr::write(&mut buffer[index], value); }
+        self.length += 1;
+        Ok(())
+    }
+
+    /// Insert `value` at `index`, shifting the elements after it.
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.length, "Insertion index out of bound");
+        self.try_reserve(1).expect("Could not request buffer");
+        let elements = self.buffer.as_mut().unwrap().as_mut_ptr();
+        unsafe {
+            std::ptr::copy(elements.add(index), elements.add(index + 1), self.length - index);
+            std::ptr::write(elements.add(index), value);
+        }
+        self.length += 1;
+    }
+
+    /// Remove the element at `index` and return it, shifting the elements
+    /// after it. Shrink the buffer if needed.
+    ///
+    /// Panics if `index` is out of bound.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.length, "Removal index out of bound");
+        let mut buffer = self.buffer.take().unwrap();
+        let elements = buffer.as_mut_ptr();
+        let value = unsafe {
+            let value = std::ptr::read(elements.add(index));
+            std::ptr::copy(elements.add(index + 1), elements.add(index), self.length - index - 1);
+            value
+        };
+        self.length -= 1;
+        self.try_shrink(buffer);
+        value
+    }
+
+    /// Insert `value` into this sorted vector after the elements equal to
+    /// it and return its index.
+    pub fn insert_sorted(&mut self, value: T) -> usize where T: Ord {
+        let index = self.partition_point(|elem| *elem <= value);
+        self.insert(index, value);
+        index
+    }
+
+    /// Insert `value` into this vector sorted by `key` after the elements
+    /// with an equal key and return its index.
+    pub fn insert_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, value: T, mut key: F) -> usize {
+        let value_key = key(&value);
+        let index = self.partition_point(|elem| key(elem) <= value_key);
+        self.insert(index, value);
+        index
+    }
+
+    /// Replace the element equal to `value` in this sorted vector and
+    /// return it, or insert `value` in order if there is none.
+    pub fn upsert_sorted(&mut self, value: T) -> Option<T> where T: Ord {
+        match self.binary_search(&value) {
+            Ok(index) => Some(std::mem::replace(&mut self[index], value)),
+            Err(index) => {
+                self.insert(index, value);
+                None
+            }
+        }
+    }
+
+    /// Replace the element with the same key as `value` in this vector
+    /// sorted by `key` and return it, or insert `value` in order if there is none.
+    pub fn upsert_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, value: T, mut key: F) -> Option<T> {
+        let value_key = key(&value);
+        match self.binary_search_by_key(&value_key, key) {
+            Ok(index) => Some(std::mem::replace(&mut self[index], value)),
+            Err(index) => {
+                self.insert(index, value);
+                None
+            }
+        }
+    }
+
+    /// Append clones of `values`, expanding the internal buffer if needed.
+    pub fn extend_from_slice(&mut self, values: &[T]) where T: Clone {
+        self.try_reserve(values.len()).expect("Could not request buffer");
+        for x in values {
+            self.push(x.clone());
+        }
+    }
+
+    /// Append `values` with a single memcpy, expanding the internal buffer
+    /// if needed.
+    pub fn extend_from_slice_copy(&mut self, values: &[T]) where T: Copy {
+        self.try_reserve(values.len()).expect("Could not request buffer");
+        let elements = self.buffer.as_mut().unwrap().as_mut_ptr();
+        unsafe { std::ptr::copy_nonoverlapping(values.as_ptr(), elements.add(self.length), values.len()); }
+        self.length += values.len();
+    }
+
+    /// Clone this vector with a single memcpy, instead of cloning its
+    /// elements one by one like `clone`.
+    pub fn clone_copy(&self) -> Self where T: Copy {
+        self.clone_with(|source, slots| {
+            unsafe { std::ptr::copy_nonoverlapping(source.as_ptr(), slots.as_mut_ptr(), source.len()); }
+        })
+    }
+
+    /// Rent a buffer as large as this vector's and fill its first `length`
+    /// slots from the elements with `fill`.
+    fn clone_with<F: FnOnce(&[T], &mut [T])>(&self, fill: F) -> Self {
+        let buffer = self.buffer.as_ref().map(|buffer| {
+            let mut new_buffer = unsafe { self.rent_buffer(buffer.len()) }
+                .expect("Could not request buffer");
+            fill(&buffer[..self.length], &mut new_buffer);
+            new_buffer
+        });
+        Self{
+            empty_buffer: [],
+            pool: self.pool.clone(),
+            buffer,
+            length: self.length,
+            tag: self.tag,
+        }
+    }
+
+    /// Make room for at least `additional` more elements.
+    ///
+    /// The buffer keeps doubling like with `push`. Fails without touching the
+    /// vector if the pool can't supply a big enough buffer.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ArrayPoolError> {
+        let required = self.length.checked_add(additional).ok_or(ArrayPoolError::MaxChunkSizeNotSufficient)?;
+        let capacity = self.capacity();
+        if required <= capacity { return Ok(()); }
+        let mut new_buffer = if capacity == 0 && required <= self.pool.min_size() {
+            unsafe { self.rent_buffer(0) }?
+        } else {
+            unsafe { self.rent_buffer(required.max(capacity * 2)) }?
+        };
+        if let Some(old_buffer) = self.buffer.take() {
+            // Moving the elements leaves the old buffer logically uninitialized
+            unsafe {
+                std::ptr::copy_nonoverlapping(old_buffer.as_ptr(), new_buffer.as_mut_ptr(), self.length);
+            }
+            self.pool.return_buffer(old_buffer);
+        }
+        self.buffer = Some(new_buffer);
+        Ok(())
+    }
+
     /// Get the length of this vector.
     pub fn len(&self) -> usize {
         self.length
     }
 
+    /// Check whether this vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     /// Get the capacity of this vector.
     pub fn capacity(&self) -> usize {
         if let Some(buffer) = &self.buffer{
@@ -63,7 +298,7 @@ impl<T: Send> PooledVec<T> {
         } else { 0 }
     }
 
-    fn try_shrink(&mut self, mut buffer: BorrowingSlice<T>) {
+    fn try_shrink(&mut self, mut buffer: P::Buffer) {
         let len = self.length;
         let cap = buffer.len();
         if self.pool.min_size() < cap && len * 2 < cap {
@@ -75,12 +310,12 @@ impl<T: Send> PooledVec<T> {
     /// Pop the last element from this vector and return it.
     /// Shrink the buffer if needed.
     pub fn pop(&mut self) -> Option<T> {
-        let mut curr: Option<BorrowingSlice<T>> = None;
+        let mut curr: Option<P::Buffer> = None;
         swap(&mut curr, &mut self.buffer);
-        if let Some(mut buffer) = curr {
+        if let Some(buffer) = curr {
             if self.length == 0 { return None; }
             self.length -= 1;
-            let return_value = unsafe { std::ptr::read(&mut buffer[self.length]) };
+            let return_value = unsafe { std::ptr::read(&buffer[self.length]) };
             self.try_shrink(buffer);
             Some(return_value)
         } else { None }
@@ -88,14 +323,14 @@ impl<T: Send> PooledVec<T> {
 
     /// Clear all elements in this vector and return its last length.
     pub fn clear(&mut self) -> usize {
-        let mut curr: Option<BorrowingSlice<T>> = None;
+        let mut curr: Option<P::Buffer> = None;
         swap(&mut curr, &mut self.buffer);
         if let Some(mut buffer) = curr{
             unsafe {
                 for i in 0..self.len(){
                     drop_in_place(&mut buffer[i])
                 }
-                drop(buffer);
+                self.pool.return_buffer(buffer);
                 let old_length = self.length;
                 self.length = 0;
                 old_length
@@ -103,6 +338,33 @@ impl<T: Send> PooledVec<T> {
         } else { 0 }
     }
 
+    /// Freeze the elements of this vector into a read-only slice that is
+    /// cheap to clone and share with readers, and leave the vector empty.
+    ///
+    /// The vector keeps appending into a fresh buffer as large as the
+    /// frozen one, rented right away if the pool can supply it.
+    pub fn snapshot(&mut self) -> SharedSlice<T, P> {
+        let fresh = match self.capacity() {
+            0 => None,
+            capacity => unsafe { self.rent_buffer(capacity) }.ok(),
+        };
+        let frozen = std::mem::replace(&mut self.buffer, fresh);
+        let length = std::mem::take(&mut self.length);
+        SharedSlice::new(self.pool.clone(), frozen, length)
+    }
+
+    /// Drop the elements past `len`, keeping the buffer. Does nothing if
+    /// the vector is not longer than `len`.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.length { return; }
+        let old_length = self.length;
+        // Leak rather than double-drop if an element panics
+        self.length = len;
+        if let Some(buffer) = &mut self.buffer {
+            unsafe { drop_in_place(&mut buffer[len..old_length]); }
+        }
+    }
+
     /// Gets a reference to an element at a specific index,
     /// may return `None` if index is out of bound.
     pub fn at(&self, index: usize) -> Option<&T> {
@@ -116,9 +378,210 @@ impl<T: Send> PooledVec<T> {
         if index >= self.length { return None; }
         Some(&mut self[index])
     }
+
+    /// Gets mutable references to the elements at several indices at once,
+    /// may return `None` if an index is out of bound or repeated.
+    pub fn get_many_mut<const N: usize>(&mut self, indices: [usize; N]) -> Option<[&mut T; N]> {
+        for (i, index) in indices.iter().enumerate() {
+            if *index >= self.length || indices[..i].contains(index) { return None; }
+        }
+        let elements = self.as_mut_ptr();
+        // Every index is in bound and distinct, the references never alias
+        Some(indices.map(|index| unsafe { &mut *elements.add(index) }))
+    }
+
+    /// Gets a cursor placed before the element at `index`, for editing
+    /// around it, see `CursorMut`.
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn cursor_mut(&mut self, index: usize) -> CursorMut<'_, T, P> {
+        assert!(index <= self.length, "Cursor index out of bound");
+        let tail = self.length - index;
+        let capacity = self.capacity();
+        if let Some(buffer) = &mut self.buffer {
+            let elements = buffer.as_mut_ptr();
+            unsafe { std::ptr::copy(elements.add(index), elements.add(capacity - tail), tail); }
+        }
+        // Elements past the gap leak rather than double-drop if the cursor is forgotten
+        self.length = index;
+        CursorMut { vec: self, front: index, tail }
+    }
+}
+
+/// Edits a `PooledVec` around a position, see `PooledVec::cursor_mut`.
+///
+/// The cursor sits between two elements, the one after it is the current
+/// element. It keeps a gap of free slots at its position so inserting and
+/// removing there never shifts the other elements, only moving the cursor
+/// does. The gap is closed when the cursor is dropped.
+pub struct CursorMut<'a, T, P: PoolBackend<T> = ArrayPool<T>> {
+    vec: &'a mut PooledVec<T, P>,
+    front: usize,
+    tail: usize,
+}
+
+impl<T, P: PoolBackend<T>> CursorMut<'_, T, P> {
+    fn elements(&mut self) -> *mut T {
+        match &mut self.vec.buffer {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => self.vec.empty_buffer.as_mut_ptr(),
+        }
+    }
+
+    fn back(&self) -> usize {
+        self.vec.capacity() - self.tail
+    }
+
+    /// Make room for at least one more element in the gap.
+    fn reserve_gap(&mut self) {
+        let capacity = self.vec.capacity();
+        if self.front + self.tail < capacity { return; }
+        let mut new_buffer = unsafe { self.vec.rent_buffer(capacity * 2) }.expect("Could not request buffer");
+        let back = self.back();
+        let new_back = new_buffer.len() - self.tail;
+        let elements = self.elements();
+        unsafe {
+            std::ptr::copy_nonoverlapping(elements, new_buffer.as_mut_ptr(), self.front);
+            std::ptr::copy_nonoverlapping(elements.add(back), new_buffer.as_mut_ptr().add(new_back), self.tail);
+        }
+        if let Some(old_buffer) = self.vec.buffer.replace(new_buffer) {
+            self.vec.pool.return_buffer(old_buffer);
+        }
+    }
+
+    /// Gets the number of elements before the cursor.
+    pub fn index(&self) -> usize {
+        self.front
+    }
+
+    /// Gets the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.front + self.tail
+    }
+
+    /// Check whether the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the element after the cursor, `None` at the end.
+    pub fn current(&mut self) -> Option<&mut T> {
+        if self.tail == 0 { return None; }
+        let back = self.back();
+        Some(unsafe { &mut *self.elements().add(back) })
+    }
+
+    /// Move the cursor past the current element, `false` at the end.
+    pub fn move_next(&mut self) -> bool {
+        if self.tail == 0 { return false; }
+        self.seek(self.front + 1);
+        true
+    }
+
+    /// Move the cursor before the previous element, `false` at the start.
+    pub fn move_prev(&mut self) -> bool {
+        if self.front == 0 { return false; }
+        self.seek(self.front - 1);
+        true
+    }
+
+    /// Move the cursor before the element at `index`, shifting the
+    /// elements in between across the gap.
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn seek(&mut self, index: usize) {
+        assert!(index <= self.len(), "Cursor index out of bound");
+        let back = self.back();
+        let elements = self.elements();
+        unsafe {
+            if index < self.front {
+                let moved = self.front - index;
+                std::ptr::copy(elements.add(index), elements.add(back - moved), moved);
+            } else {
+                let moved = index - self.front;
+                std::ptr::copy(elements.add(back), elements.add(self.front), moved);
+            }
+        }
+        self.tail = self.len() - index;
+        self.front = index;
+        self.vec.length = index;
+    }
+
+    /// Insert `value` before the cursor, the cursor moves past it.
+    pub fn insert_before(&mut self, value: T) {
+        self.reserve_gap();
+        let front = self.front;
+        unsafe { std::ptr::write(self.elements().add(front), value); }
+        self.front += 1;
+        self.vec.length = self.front;
+    }
+
+    /// Insert `value` after the cursor, it becomes the current element.
+    pub fn insert_after(&mut self, value: T) {
+        self.reserve_gap();
+        let back = self.back();
+        unsafe { std::ptr::write(self.elements().add(back - 1), value); }
+        self.tail += 1;
+    }
+
+    /// Remove the current element and return it, `None` at the end.
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.tail == 0 { return None; }
+        let back = self.back();
+        self.tail -= 1;
+        Some(unsafe { std::ptr::read(self.elements().add(back)) })
+    }
+}
+
+impl<T, P: PoolBackend<T>> Drop for CursorMut<'_, T, P> {
+    fn drop(&mut self) {
+        let back = self.back();
+        let front = self.front;
+        let elements = self.elements();
+        unsafe { std::ptr::copy(elements.add(back), elements.add(front), self.tail); }
+        self.vec.length = self.front + self.tail;
+    }
+}
+
+impl<T: Send + 'static> PooledVec<T> {
+    /// Create a new vector renting from the process-wide pool of `T`, see
+    /// `global_pool`.
+    pub fn new() -> Self {
+        Self::create(global_pool())
+    }
+}
+
+impl<T: Send + 'static> Default for PooledVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> FromIterator<T> for PooledVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect_pooled(&global_pool())
+    }
 }
 
-impl<T: Send> Deref for PooledVec<T>{
+#[cfg(feature = "bytemuck")]
+impl<T: bytemuck::Pod, P: PoolBackend<T>> PooledVec<T, P> {
+    /// View the elements of this vector as a slice of `U`, sharing its storage.
+    ///
+    /// Fails if the size or alignment of the elements doesn't fit `U`.
+    pub fn cast<U: bytemuck::Pod>(&self) -> Result<&[U], bytemuck::PodCastError> {
+        bytemuck::try_cast_slice(self.deref())
+    }
+
+    /// View the elements of this vector as a mutable slice of `U`, sharing
+    /// its storage.
+    ///
+    /// Fails if the size or alignment of the elements doesn't fit `U`.
+    pub fn cast_mut<U: bytemuck::Pod>(&mut self) -> Result<&mut [U], bytemuck::PodCastError> {
+        bytemuck::try_cast_slice_mut(self.deref_mut())
+    }
+}
+
+impl<T, P: PoolBackend<T>> Deref for PooledVec<T, P>{
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
@@ -129,7 +592,7 @@ impl<T: Send> Deref for PooledVec<T>{
     }
 }
 
-impl<T: Send> DerefMut for PooledVec<T>{
+impl<T, P: PoolBackend<T>> DerefMut for PooledVec<T, P>{
     fn deref_mut(&mut self) -> &mut Self::Target {
         match &mut self.buffer {
             Some(r) => &mut r.deref_mut()[0..self.length],
@@ -138,18 +601,73 @@ impl<T: Send> DerefMut for PooledVec<T>{
     }
 }
 
-impl<T: Send + Clone> Clone for PooledVec<T>{
+impl<T, P: PoolBackend<T>> AsRef<[T]> for PooledVec<T, P> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, P: PoolBackend<T>> AsMut<[T]> for PooledVec<T, P> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, P: PoolBackend<T>> Borrow<[T]> for PooledVec<T, P> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, P: PoolBackend<T>> BorrowMut<[T]> for PooledVec<T, P> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, P: PoolBackend<T>, I: SliceIndex<[T]>> Index<I> for PooledVec<T, P> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.deref()[index]
+    }
+}
+
+impl<T, P: PoolBackend<T>, I: SliceIndex<[T]>> IndexMut<I> for PooledVec<T, P> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.deref_mut()[index]
+    }
+}
+
+impl<T: Clone, P: PoolBackend<T>> Clone for PooledVec<T, P>{
     fn clone(&self) -> Self {
-        Self{
-            empty_buffer: [],
-            pool: self.pool.clone(),
-            buffer: self.buffer.clone(),
-            length: self.length,
+        self.clone_with(|source, slots| {
+            // Only the first `length` slots are initialized
+            let mut guard = unsafe { InitGuard::new(slots) };
+            for x in source {
+                guard.push(x.clone());
+            }
+            guard.finish();
+        })
+    }
+
+    /// Clone `source` into the buffer of this vector if it is large enough
+    /// and both vectors have the same tag, instead of renting a new one.
+    fn clone_from(&mut self, source: &Self) {
+        if self.capacity() < source.length || self.tag != source.tag {
+            *self = source.clone();
+            return;
+        }
+        let common = self.length.min(source.length);
+        self.truncate(common);
+        self[..common].clone_from_slice(&source[..common]);
+        for x in &source[common..] {
+            self.push(x.clone());
         }
     }
 }
 
-impl<T: Send + Display> Display for PooledVec<T> {
+impl<T: Display, P: PoolBackend<T>> Display for PooledVec<T, P> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "[ ")?;
 
@@ -167,3 +685,13 @@ impl<T: Send + Display> Display for PooledVec<T> {
         Ok(())
     }
 }
+
+impl<T: Debug, P: PoolBackend<T>> Debug for PooledVec<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledVec")
+            .field("len", &self.length)
+            .field("capacity", &self.capacity())
+            .field("items", &self.deref())
+            .finish()
+    }
+}